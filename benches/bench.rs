@@ -8,7 +8,7 @@ use helpers::fixed_client::{
 	http_client, rpc_params, ws_client, ws_handshake, ArrayParams, BatchRequestBuilder, ClientT, HeaderMap,
 	SubscriptionClientT,
 };
-use helpers::{KIB, SUB_METHOD_NAME, UNSUB_METHOD_NAME};
+use helpers::{KIB, MIB, SUB_METHOD_NAME, SYNC_MEM_CALL, UNSUB_METHOD_NAME};
 use jsonrpsee::types::{Id, RequestSer};
 use pprof::criterion::{Output, PProfProfiler};
 use tokio::runtime::Runtime as TokioRuntime;
@@ -30,7 +30,12 @@ fn measurement_mid() -> Duration {
 criterion_group!(
 	name = types_benches;
 	config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
-	targets = jsonrpsee_types_v2
+	targets = jsonrpsee_types_v2, json_codecs
+);
+criterion_group!(
+	name = http_client_benches;
+	config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+	targets = http_read_buffer_size
 );
 criterion_group!(
 	name = sync_benches;
@@ -69,6 +74,7 @@ criterion_group!(
 );
 criterion_main!(
 	types_benches,
+	http_client_benches,
 	sync_benches,
 	sync_benches_mid,
 	sync_benches_slow,
@@ -101,6 +107,55 @@ impl RequestType {
 	}
 }
 
+/// Compare the JSON decoding backends of the HTTP client on a large response.
+pub fn json_codecs(crit: &mut Criterion) {
+	use jsonrpsee_http_client::codec::{JsonCodec, SerdeJson, SimdJson};
+
+	let result: Vec<String> = (0..10_000).map(|i| format!("item-{}", i)).collect();
+	let response = serde_json::to_vec(&serde_json::json!({ "jsonrpc": "2.0", "result": result, "id": 0 })).unwrap();
+
+	let mut group = crit.benchmark_group("json_codecs");
+	group.throughput(Throughput::Bytes(response.len() as u64));
+	group.bench_function("serde_json", |b| {
+		b.iter_batched_ref(
+			|| response.clone(),
+			|bytes| SerdeJson::decode::<serde_json::Value>(bytes).unwrap(),
+			BatchSize::SmallInput,
+		)
+	});
+	group.bench_function("simd_json", |b| {
+		b.iter_batched_ref(
+			|| response.clone(),
+			|bytes| SimdJson::decode::<serde_json::Value>(bytes).unwrap(),
+			BatchSize::SmallInput,
+		)
+	});
+	group.finish();
+}
+
+/// Compare read buffer sizes of the HTTP client on a 1 MiB response.
+pub fn http_read_buffer_size(crit: &mut Criterion) {
+	use jsonrpsee::core::client::ClientT;
+	use jsonrpsee::core::params::ArrayParams;
+	use jsonrpsee_http_client::HttpClientBuilder;
+
+	let rt = TokioRuntime::new().unwrap();
+	let (url, _server) = rt.block_on(helpers::http_server(rt.handle().clone()));
+
+	let mut group = crit.benchmark_group("http_read_buffer_size");
+	group.throughput(Throughput::Bytes(MIB as u64));
+	// The minimum accepted by hyper, the default and a buffer larger than the response.
+	for size in [8 * KIB, 8 * KIB + 400 * KIB, 2 * MIB] {
+		let client = HttpClientBuilder::default().read_buffer_size(size).build(&url).unwrap();
+		group.bench_function(format!("{}kb", size / KIB), |b| {
+			b.to_async(&rt).iter(|| async {
+				black_box(client.request::<String, ArrayParams>(SYNC_MEM_CALL, ArrayParams::new()).await.unwrap());
+			})
+		});
+	}
+	group.finish();
+}
+
 fn v2_serialize(req: RequestSer<'_>) -> String {
 	serde_json::to_string(&req).unwrap()
 }
@@ -0,0 +1,53 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Answer the authentication challenges of servers, e.g. to refresh an expired OAuth token.
+//!
+//! Register an [`AuthHandler`] with [`HttpClientBuilder::set_auth_handler`](crate::HttpClientBuilder::set_auth_handler)
+//! or in the [`HttpTransportClientConfig`](crate::transport::HttpTransportClientConfig). Without a handler, a
+//! `401 Unauthorized` response fails the request.
+
+use crate::transport::Error;
+use hyper::http::HeaderMap;
+use std::fmt;
+
+/// Max number of times a request is sent again after a `401 Unauthorized` response, such that a handler that
+/// keeps asking for a retry with credentials the server rejects doesn't loop forever.
+pub const MAX_AUTH_ATTEMPTS: u32 = 3;
+
+/// Handler of the `WWW-Authenticate` challenges of `401 Unauthorized` responses.
+pub trait AuthHandler: fmt::Debug + Send + Sync + 'static {
+	/// Handle `challenge`, the value of a `WWW-Authenticate` header, e.g. `Bearer error="invalid_token"` or
+	/// `Negotiate`.
+	///
+	/// Add the credentials to `headers`, which are added to the request when it's sent again and replace the
+	/// headers with the same name, and return `true` to send it again. Return `false` to leave the challenge to
+	/// the next `WWW-Authenticate` header, if any, or fail the request with the `401` response. An error fails the
+	/// request.
+	///
+	/// The headers added for earlier challenges of the same request are kept.
+	fn handle_challenge(&self, challenge: &str, headers: &mut HeaderMap) -> Result<bool, Error>;
+}
@@ -25,24 +25,37 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::borrow::Cow as StdCow;
+use std::collections::HashSet;
 use std::error::Error as StdError;
 use std::fmt;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::transport::{Error as TransportError, HttpBackend, HttpTransportClient};
+use crate::connector::CustomConnector;
+use crate::executor::{self, Executor};
+use crate::limits::{Limits, MethodLimits};
+use crate::observer::Observer;
+use crate::transport::{
+	AddressFamily, Error as TransportError, HttpBackend, HttpTransportClient, HttpTransportClientConfig, HttpVersion,
+	KeepAliveConfig, PoolConfig, RequestOptions, RetryConfig, ReuseStats, TargetRewriter, TrailingSlash,
+	TransportMetrics,
+};
 use crate::types::{NotificationSer, RequestSer, Response};
 use async_trait::async_trait;
 use hyper::body::HttpBody;
-use hyper::http::HeaderMap;
+use hyper::client::connect::Connection;
+use hyper::http::{HeaderMap, HeaderValue};
 use hyper::Body;
+#[cfg(feature = "__tls")]
+use jsonrpsee_core::client::CertificateStore;
 use jsonrpsee_core::client::{
-	generate_batch_id_range, BatchResponse, CertificateStore, ClientT, IdKind, RequestIdManager, Subscription,
-	SubscriptionClientT,
+	generate_batch_id_range, BatchResponse, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT,
 };
 use jsonrpsee_core::params::BatchRequestBuilder;
 use jsonrpsee_core::traits::ToRpcParams;
-use jsonrpsee_core::{Error, JsonRawValue, TEN_MB_SIZE_BYTES};
+use jsonrpsee_core::{Error, JsonRawValue};
+use jsonrpsee_types::error::INTERNAL_ERROR_CODE;
 use jsonrpsee_types::{ErrorObject, InvalidRequestId, ResponseSuccess, TwoPointZero};
 use serde::de::DeserializeOwned;
 use tower::layer::util::Identity;
@@ -74,29 +87,26 @@ use tracing::instrument;
 /// ```
 #[derive(Debug)]
 pub struct HttpClientBuilder<L = Identity> {
-	max_request_size: u32,
-	max_response_size: u32,
+	transport: HttpTransportClientConfig,
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
-	certificate_store: CertificateStore,
 	id_kind: IdKind,
-	max_log_length: u32,
-	headers: HeaderMap,
 	service_builder: tower::ServiceBuilder<L>,
 	ignore_resp_id: bool,
-	http_only: bool,
+	batch_id_policy: BatchIdPolicy,
+	method_limits: MethodLimits,
 }
 
 impl<L> HttpClientBuilder<L> {
 	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
 	pub fn max_request_size(mut self, size: u32) -> Self {
-		self.max_request_size = size;
+		self.transport.max_request_size = size;
 		self
 	}
 
 	/// Set the maximum size of a response in bytes. Default is 10 MiB.
 	pub fn max_response_size(mut self, size: u32) -> Self {
-		self.max_response_size = size;
+		self.transport.max_response_size = size;
 		self
 	}
 
@@ -106,6 +116,37 @@ impl<L> HttpClientBuilder<L> {
 		self
 	}
 
+	/// Set the max time to wait for the response headers of each HTTP request, where each redirect hop gets a
+	/// fresh timeout (disabled by default).
+	///
+	/// Unlike [`HttpClientBuilder::request_timeout`], which bounds the whole call, this detects a server that
+	/// doesn't respond and reports it as a transport error.
+	pub fn request_timeout_per_hop(mut self, timeout: Duration) -> Self {
+		self.transport.request_timeout = Some(timeout);
+		self
+	}
+
+	/// Set the max number of new connections started per second (unlimited by default).
+	///
+	/// Unlike [`HttpClientBuilder::max_concurrent_requests`], this only delays requests that need a new
+	/// connection, see [`HttpTransportClientConfig::max_connections_per_second`].
+	pub fn max_connections_per_second(mut self, max: NonZeroU32) -> Self {
+		self.transport.max_connections_per_second = Some(max);
+		self
+	}
+
+	/// Probe the max request body size of the server and read it from the response header `header_name` (disabled
+	/// by default), see [`HttpTransportClientConfig::size_probe_header`].
+	///
+	/// The server is probed before the first request. A failed probe is logged and treated as no server limit for
+	/// that request, and the next request probes again. Call [`HttpClient::probe_request_size`] after building the
+	/// client as the startup check: it fails if the probe fails or the configured max request size exceeds the size
+	/// advertised by the server. Building the client fails if `header_name` isn't a valid header name.
+	pub fn with_size_probe(mut self, header_name: impl Into<String>) -> Self {
+		self.transport.size_probe_header = Some(header_name.into());
+		self
+	}
+
 	/// Set max concurrent requests.
 	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
 		self.max_concurrent_requests = max;
@@ -122,7 +163,7 @@ impl<L> HttpClientBuilder<L> {
 	/// This requires the optional `native-tls` feature.
 	#[cfg(feature = "native-tls")]
 	pub fn use_native_rustls(mut self) -> Self {
-		self.certificate_store = CertificateStore::Native;
+		self.transport.certificate_store = CertificateStore::Native;
 		self
 	}
 
@@ -136,7 +177,70 @@ impl<L> HttpClientBuilder<L> {
 	/// This requires the optional `webpki-tls` feature.
 	#[cfg(feature = "webpki-tls")]
 	pub fn use_webpki_rustls(mut self) -> Self {
-		self.certificate_store = CertificateStore::WebPki;
+		self.transport.certificate_store = CertificateStore::WebPki;
+		self
+	}
+
+	/// Trust the PEM encoded root certificates of `pem` in addition to the ones of the certificate store, e.g. the
+	/// certificate of an internal CA (default is none).
+	///
+	/// Building the client fails with `InvalidCertficateStore` if the certificates can't be parsed.
+	///
+	/// # Optional
+	///
+	/// This requires one of the optional `native-tls` or `webpki-tls` features.
+	#[cfg(feature = "__tls")]
+	pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+		self.transport.root_certificates.push(pem.into());
+		self
+	}
+
+	/// Only allow the given TLS cipher suites (default is the safe defaults of `rustls`).
+	///
+	/// Building the client fails if the list is empty or contains no suite usable with TLS 1.2 or 1.3.
+	///
+	/// # Optional
+	///
+	/// This requires one of the optional `native-tls` or `webpki-tls` features.
+	#[cfg(feature = "__tls")]
+	pub fn cipher_suites(mut self, suites: Vec<rustls::SupportedCipherSuite>) -> Self {
+		self.transport.cipher_suites = Some(suites);
+		self
+	}
+
+	/// Create the TLS connector, which loads the root certificates, on the first connection instead of in
+	/// [`HttpClientBuilder::build`] (default is disabled).
+	///
+	/// The connector is shared by all clients built from clones of this builder.
+	///
+	/// This requires one of the optional `native-tls` or `webpki-tls` features.
+	#[cfg(feature = "__tls")]
+	pub fn lazy_tls(mut self, lazy: bool) -> Self {
+		self.transport.lazy_tls = if lazy { Some(crate::transport::LazyTls::new()) } else { None };
+		self
+	}
+
+	/// Resume TLS sessions with the session tickets stored in `cache`, which can be shared with other clients
+	/// (default is a cache per client).
+	///
+	/// Clients that are created for a few calls each and share a cache skip most of the full TLS handshakes.
+	///
+	/// This requires one of the optional `native-tls` or `webpki-tls` features.
+	#[cfg(feature = "__tls")]
+	pub fn tls_session_cache(mut self, cache: crate::transport::TlsSessionCache) -> Self {
+		self.transport.tls_session_cache = Some(cache);
+		self
+	}
+
+	/// Present the client certificate of `identity` to servers that require client authentication, i.e. mutual
+	/// TLS (default is none).
+	///
+	/// See [`ClientIdentity::from_pem`](crate::transport::ClientIdentity::from_pem) to load the certificate and key.
+	///
+	/// This requires one of the optional `native-tls` or `webpki-tls` features.
+	#[cfg(feature = "__tls")]
+	pub fn client_identity(mut self, identity: crate::transport::ClientIdentity) -> Self {
+		self.transport.client_identity = Some(identity);
 		self
 	}
 
@@ -146,16 +250,24 @@ impl<L> HttpClientBuilder<L> {
 		self
 	}
 
+	/// Do not check that the `id` of the response matches the `id` of the request (default is false).
 	pub fn ignore_response_id(mut self, ignore: bool) -> Self {
 		self.ignore_resp_id = ignore;
 		self
 	}
 
+	/// Configure how the response of a batch request is matched against the requested ids
+	/// (default is [`BatchIdPolicy::StrictIds`]).
+	pub fn batch_id_policy(mut self, policy: BatchIdPolicy) -> Self {
+		self.batch_id_policy = policy;
+		self
+	}
+
 	/// Max length for logging for requests and responses in number characters.
 	///
 	/// Logs bigger than this limit will be truncated.
 	pub fn set_max_logging_length(mut self, max: u32) -> Self {
-		self.max_log_length = max;
+		self.transport.max_log_length = max;
 		self
 	}
 
@@ -163,29 +275,341 @@ impl<L> HttpClientBuilder<L> {
 	///
 	/// The caller is responsible for checking that the headers do not conflict or are duplicated.
 	pub fn set_headers(mut self, headers: HeaderMap) -> Self {
-		self.headers = headers;
+		self.transport.headers = headers;
+		self
+	}
+
+	/// Send the default `Content-Type: application/json` and `Accept: application/json` headers (default is true).
+	///
+	/// Headers set with [`HttpClientBuilder::set_headers`] replace the defaults with the same name either way.
+	pub fn default_headers(mut self, enabled: bool) -> Self {
+		self.transport.default_headers = enabled;
 		self
 	}
 
+	/// Replace 'https' with 'http' in the target and in redirects (default is false).
 	pub fn set_http_only(mut self, http_only: bool) -> Self {
-		self.http_only = http_only;
+		self.transport.http_only = http_only;
+		self
+	}
+
+	/// Refuse to talk to the server without TLS (default is false).
+	///
+	/// When enabled, building the client fails if the target doesn't use 'https' or if
+	/// [`HttpClientBuilder::set_http_only`] is enabled, and redirects to targets without TLS are rejected.
+	pub fn require_tls(mut self, require: bool) -> Self {
+		self.transport.require_tls = require;
+		self
+	}
+
+	/// Ping the server with a JSON-RPC notification with the given `method` whenever no request
+	/// has been in flight for `interval` (disabled by default).
+	///
+	/// This keeps the connection in hyper's connection pool warm, such that the next request doesn't
+	/// have to reconnect after the connection was dropped by a NAT or proxy with an aggressive idle timeout.
+	/// The response to the ping is ignored, and a ping times out after
+	/// [`HttpClientBuilder::request_timeout_per_hop`], or after `interval` if unset. No pings are sent if connection reuse is disabled with
+	/// [`PoolConfig::max_idle_per_host`].
+	pub fn keep_alive(mut self, interval: Duration, method: impl Into<String>) -> Self {
+		self.transport.keep_alive = Some(KeepAliveConfig { interval, method: method.into() });
+		self
+	}
+
+	/// Retry requests that fail with a connection error before any response was received (disabled by default).
+	pub fn retry_config(mut self, retry: RetryConfig) -> Self {
+		self.transport.retry = Some(retry);
+		self
+	}
+
+	/// Set the max number of times an idempotent request is sent again on a new connection after the server shut
+	/// down the HTTP/2 connection with `GOAWAY` (default is 1).
+	///
+	/// If `0`, such requests fail right away.
+	pub fn go_away_retries(mut self, max: u32) -> Self {
+		self.transport.go_away_retries = max;
+		self
+	}
+
+	/// Set the max number of redirects to follow (default is 32).
+	///
+	/// If `0`, redirects aren't followed and a redirect response is returned as an error.
+	pub fn max_redirects(mut self, max: u32) -> Self {
+		self.transport.max_redirects = max;
+		self
+	}
+
+	/// Set the max number of distinct hosts a request and its redirects may go to, including the target
+	/// (default is unbounded).
+	pub fn max_redirect_hosts(mut self, max: u32) -> Self {
+		self.transport.max_redirect_hosts = Some(max);
+		self
+	}
+
+	/// Only follow redirects to paths under `prefix`, matched by whole path segments (default is unrestricted).
+	///
+	/// A redirect out of the prefix is returned as an error.
+	pub fn redirect_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+		self.transport.redirect_path_prefix = Some(prefix.into());
+		self
+	}
+
+	/// Set the max length of the target URL and of redirect targets (default is 8 KiB).
+	pub fn max_url_length(mut self, max: u32) -> Self {
+		self.transport.max_url_length = max;
+		self
+	}
+
+	/// Set how to treat a trailing slash in the path of the target (default is [`TrailingSlash::Preserve`]).
+	pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+		self.transport.trailing_slash = trailing_slash;
+		self
+	}
+
+	/// Send an `Accept-Encoding` header, `gzip, deflate` by default, and decompress gzip and deflate responses
+	/// (disabled by default).
+	///
+	/// The max response size applies to the decompressed body.
+	pub fn accept_compression(mut self, enabled: bool) -> Self {
+		self.transport.accept_compression = enabled;
+		self
+	}
+
+	/// Set the value of the `Accept-Encoding` header sent if [`HttpClientBuilder::accept_compression`] is enabled
+	/// (default is `gzip, deflate`).
+	pub fn accept_encoding(mut self, accept_encoding: crate::transport::AcceptEncoding) -> Self {
+		self.transport.accept_encoding = accept_encoding;
+		self
+	}
+
+	/// Set the content encodings accepted in responses besides `identity` (default is the encodings in the
+	/// `Accept-Encoding` header if [`HttpClientBuilder::accept_compression`] is enabled, and any otherwise).
+	///
+	/// Responses with any other `Content-Encoding` are rejected.
+	pub fn accepted_encodings(mut self, encodings: impl IntoIterator<Item = impl Into<String>>) -> Self {
+		self.transport.accepted_encodings = Some(encodings.into_iter().map(Into::into).collect());
+		self
+	}
+
+	/// Only connect to the server over the given IP version (default is [`AddressFamily::Any`]).
+	pub fn address_family(mut self, address_family: AddressFamily) -> Self {
+		self.transport.address_family = address_family;
+		self
+	}
+
+	/// Send TCP keepalive probes on idle connections at `interval` (disabled by default).
+	pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+		self.transport.tcp_keepalive = Some(interval);
+		self
+	}
+
+	/// Set `TCP_NODELAY` on the connections (default is `false`).
+	pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+		self.transport.tcp_nodelay = nodelay;
+		self
+	}
+
+	/// Set the HTTP version used to talk to the server (default is [`HttpVersion::Http1`]).
+	pub fn http_version(mut self, http_version: HttpVersion) -> Self {
+		self.transport.http_version = http_version;
+		self
+	}
+
+	/// Connect through the proxies configured in the `http_proxy`, `https_proxy` and `no_proxy` environment
+	/// variables (default is false).
+	pub fn use_system_proxy(mut self, enabled: bool) -> Self {
+		self.transport.use_system_proxy = enabled;
+		self
+	}
+
+	/// Set the connection pool settings (default is [`PoolConfig::default`]).
+	///
+	/// Connections are never shared between clients unless [`HttpClientBuilder::allow_connection_reuse`] is
+	/// enabled, the settings only tune reuse within a client.
+	pub fn pool_config(mut self, pool: PoolConfig) -> Self {
+		self.transport.pool = pool;
+		self
+	}
+
+	/// Share the connection pool of the client with the clients created from it with [`HttpClient::with_target`]
+	/// for targets with the same scheme (default is disabled).
+	///
+	/// These clients share the request ids too, so that ids are never reused on a connection.
+	pub fn allow_connection_reuse(mut self, allow: bool) -> Self {
+		self.transport.allow_connection_reuse = allow;
+		self
+	}
+
+	/// Only allow targets and redirects to hosts that match the glob `pattern`, e.g. `*.internal.example.com`
+	/// (disabled by default).
+	///
+	/// `*` matches any number of characters and `?` matches a single character.
+	pub fn host_pattern(mut self, pattern: impl Into<String>) -> Self {
+		self.transport.host_pattern = Some(pattern.into());
+		self
+	}
+
+	/// Rewrite the target of each request with `f` (disabled by default), see [`TargetRewriter`].
+	pub fn target_rewriter(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+		self.transport.target_rewriter = Some(TargetRewriter::new(f));
+		self
+	}
+
+	/// Remove a leading UTF-8 byte order mark from responses before parsing them (default is false).
+	///
+	/// Some servers, notably .NET based ones, prefix the JSON body with a BOM which JSON parsers reject.
+	pub fn strip_bom(mut self, strip: bool) -> Self {
+		self.transport.strip_bom = strip;
+		self
+	}
+
+	/// Check that request bodies are well-formed JSON before sending them (default is false).
+	///
+	/// Useful to catch bodies that are assembled by hand, at the cost of an extra pass over each body.
+	pub fn validate_json(mut self, validate: bool) -> Self {
+		self.transport.validate_json = validate;
+		self
+	}
+
+	/// Artificially slow down sending request bodies to test server timeouts (disabled by default).
+	///
+	/// This is a testing aid only and must never be used in production.
+	#[cfg(feature = "test-utils")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+	pub fn slow_body(mut self, slow: crate::transport::SlowBody) -> Self {
+		self.transport.slow_body = Some(slow);
+		self
+	}
+
+	/// Remove the response headers with these names from the response metadata (default is none).
+	pub fn strip_response_headers(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+		self.transport.strip_response_headers = names.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Set the max number of headers in a response (default is 100).
+	pub fn max_response_headers(mut self, max: usize) -> Self {
+		self.transport.max_response_headers = max;
+		self
+	}
+
+	/// Set the max size of the headers of a request in bytes, including the headers of
+	/// [`HttpClient::with_headers`] (default is 64 KiB).
+	pub fn max_request_header_size(mut self, size: u32) -> Self {
+		self.transport.max_request_header_size = size;
+		self
+	}
+
+	/// Log a warning and notify the observer when a response is larger than `threshold` bytes (disabled by default).
+	///
+	/// Unlike [`HttpClientBuilder::max_response_size`], this doesn't fail the request.
+	pub fn response_size_warning(mut self, threshold: u32) -> Self {
+		self.transport.response_size_warning = Some(threshold);
+		self
+	}
+
+	/// Set the max number of bytes allocated up front for a response body with a known length (default is 16 KiB).
+	///
+	/// Raising this saves reallocations while reading large responses. How much is read from the connection at once is
+	/// set by [`HttpClientBuilder::read_buffer_size`].
+	pub fn initial_body_capacity(mut self, size: usize) -> Self {
+		self.transport.initial_body_capacity = size;
+		self
+	}
+
+	/// Set the max size of the read buffer of an HTTP/1.1 connection in bytes (default is about 400 KiB).
+	///
+	/// A larger buffer reads large responses from the connection with fewer syscalls. Building the client fails if
+	/// it's below 8 KiB.
+	pub fn read_buffer_size(mut self, size: usize) -> Self {
+		self.transport.read_buffer_size = size;
+		self
+	}
+
+	/// Set the callbacks to observe the transport (default is none).
+	pub fn set_observer(mut self, observer: impl Observer) -> Self {
+		self.transport.observer = Some(Arc::new(observer));
+		self
+	}
+
+	/// Invoke `f` before each retry of a request with the attempt number, its error and the delay before the next
+	/// attempt (default is none), see [`crate::observer::RetryHook`].
+	pub fn set_on_retry(mut self, f: impl Fn(crate::observer::RetryInfo<'_>) + Send + Sync + 'static) -> Self {
+		self.transport.on_retry = Some(crate::observer::RetryHook::new(f));
+		self
+	}
+
+	/// Set the handler of the authentication challenges of `401 Unauthorized` responses (default is none, such
+	/// that these responses fail the call).
+	pub fn set_auth_handler(mut self, handler: impl crate::auth::AuthHandler) -> Self {
+		self.transport.auth_handler = Some(Arc::new(handler));
+		self
+	}
+
+	/// Take the deadline of each request without one of its own from `provider` (default is none), see
+	/// [`crate::transport::DeadlineProvider`].
+	pub fn set_deadline_provider(mut self, provider: impl crate::transport::DeadlineProvider) -> Self {
+		self.transport.deadline_provider = Some(Arc::new(provider));
+		self
+	}
+
+	/// Pass the exact bytes written to and read from each connection to `tap` (default is none).
+	///
+	/// This is for debugging and tests only, see [`crate::wiretap`] for the cost.
+	///
+	/// # Optional
+	///
+	/// This requires the optional `test-utils` feature.
+	#[cfg(feature = "test-utils")]
+	pub fn set_wire_tap(mut self, tap: impl crate::wiretap::WireTap) -> Self {
+		self.transport.wire_tap = Some(Arc::new(tap));
+		self
+	}
+
+	/// Connect to `http` and `https` targets with `connector` instead of hyper's `HttpConnector` (default is
+	/// hyper's connector), see [`crate::connector`].
+	pub fn set_connector<C>(mut self, connector: C) -> Self
+	where
+		C: tower::Service<hyper::Uri> + Clone + Send + Sync + 'static,
+		C::Response: tokio::io::AsyncRead + tokio::io::AsyncWrite + Connection + Unpin + Send + 'static,
+		C::Error: Into<Box<dyn StdError + Send + Sync>>,
+		C::Future: Send + 'static,
+	{
+		self.transport.connector = Some(CustomConnector::new(connector));
+		self
+	}
+
+	/// Add the trace context of the current task to the headers of each request (default is none).
+	#[cfg(feature = "otel")]
+	pub fn set_context_injector(mut self, injector: impl crate::otel::Injector) -> Self {
+		self.transport.context_injector = Some(Arc::new(injector));
+		self
+	}
+
+	/// Set per-method size limits and timeouts (default is none), see [`MethodLimits`].
+	///
+	/// Limits that aren't set in the policy fall back to the limits configured on the builder.
+	pub fn method_limits(mut self, limits: MethodLimits) -> Self {
+		self.method_limits = limits;
+		self
+	}
+
+	/// Set the runtime used to spawn background tasks and to wait for timeouts (default is [`crate::executor::TokioExecutor`]).
+	pub fn set_executor(mut self, executor: impl Executor) -> Self {
+		self.transport.executor = Arc::new(executor);
 		self
 	}
 
 	/// Set custom tower middleware.
 	pub fn set_http_middleware<T>(self, service_builder: tower::ServiceBuilder<T>) -> HttpClientBuilder<T> {
 		HttpClientBuilder {
-			certificate_store: self.certificate_store,
+			transport: self.transport,
 			id_kind: self.id_kind,
-			headers: self.headers,
-			max_log_length: self.max_log_length,
 			max_concurrent_requests: self.max_concurrent_requests,
-			max_request_size: self.max_request_size,
-			max_response_size: self.max_response_size,
 			service_builder,
 			request_timeout: self.request_timeout,
 			ignore_resp_id: self.ignore_resp_id,
-			http_only: self.http_only,
+			batch_id_policy: self.batch_id_policy,
+			method_limits: self.method_limits,
 		}
 	}
 }
@@ -199,38 +623,59 @@ where
 	B::Error: Into<Box<dyn StdError + Send + Sync>>,
 {
 	/// Build the HTTP client with target to connect to.
+	///
+	/// ## Panics
+	///
+	/// Panics if [`HttpClientBuilder::keep_alive`] is enabled and this is called outside of `tokio` runtime context
+	/// with the default executor.
+	///
+	/// The client can't create clients for other targets, see [`HttpClientBuilder::build_retargetable`].
 	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient<S>, Error> {
+		self.build_with(|config, service_builder| HttpTransportClient::new(target, config, service_builder))
+	}
+
+	/// Build the HTTP client with target to connect to, which keeps the middleware to create clients for other
+	/// targets with [`HttpClient::with_target`].
+	///
+	/// ## Panics
+	///
+	/// Panics in the same cases as [`HttpClientBuilder::build`].
+	pub fn build_retargetable(self, target: impl AsRef<str>) -> Result<HttpClient<S>, Error>
+	where
+		L: Send + Sync + 'static,
+	{
+		self.build_with(|config, service_builder| {
+			HttpTransportClient::new_retargetable(target, config, service_builder)
+		})
+	}
+
+	fn build_with(
+		self,
+		transport: impl FnOnce(
+			HttpTransportClientConfig,
+			tower::ServiceBuilder<L>,
+		) -> Result<HttpTransportClient<S>, TransportError>,
+	) -> Result<HttpClient<S>, Error> {
 		let Self {
-			max_request_size,
-			max_response_size,
+			transport: config,
 			max_concurrent_requests,
 			request_timeout,
-			certificate_store,
 			id_kind,
-			headers,
-			max_log_length,
 			service_builder,
 			ignore_resp_id,
-			http_only,
-			..
+			batch_id_policy,
+			method_limits,
 		} = self;
 
-		let transport = HttpTransportClient::new(
-			max_request_size,
-			target,
-			max_response_size,
-			certificate_store,
-			max_log_length,
-			headers,
-			service_builder,
-			http_only,
-		)
-		.map_err(|e| Error::Transport(e.into()))?;
+		let transport = transport(config, service_builder).map_err(|e| Error::Transport(e.into()))?;
 		Ok(HttpClient {
 			transport,
 			id_manager: Arc::new(RequestIdManager::new(max_concurrent_requests, id_kind)),
 			request_timeout,
 			ignore_resp_id,
+			batch_id_policy,
+			method_limits: Arc::new(method_limits),
+			call_options: CallOptions::default(),
 		})
 	}
 }
@@ -238,17 +683,14 @@ where
 impl Default for HttpClientBuilder<Identity> {
 	fn default() -> Self {
 		Self {
-			max_request_size: TEN_MB_SIZE_BYTES,
-			max_response_size: TEN_MB_SIZE_BYTES,
+			transport: HttpTransportClientConfig::default(),
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
-			certificate_store: CertificateStore::Native,
 			id_kind: IdKind::Number,
-			max_log_length: 4096,
-			headers: HeaderMap::new(),
 			service_builder: tower::ServiceBuilder::new(),
 			ignore_resp_id: false,
-			http_only: false,
+			batch_id_policy: BatchIdPolicy::default(),
+			method_limits: MethodLimits::default(),
 		}
 	}
 }
@@ -260,6 +702,35 @@ impl HttpClientBuilder<Identity> {
 	}
 }
 
+/// How the responses of a batch request are matched against the requested ids.
+///
+/// Responses are always returned in the order of the requests, regardless of the order the server sent them in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchIdPolicy {
+	/// Fail the batch if the server sent a response for an id that wasn't requested, more than one response for
+	/// the same id or no response for a requested id.
+	#[default]
+	StrictIds,
+	/// Ignore responses for unknown ids, keep the first response for duplicated ids and report a missing response
+	/// as a failed call with an internal error.
+	Lenient,
+}
+
+/// Transport options sent with each call of a [`HttpClient`], see [`HttpClient::with_call_options`].
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+	/// Headers added to the headers of the transport, replacing the ones with the same name.
+	pub headers: HeaderMap,
+	/// Value of the `Idempotency-Key` header, see [`RequestOptions::idempotency_key`].
+	///
+	/// The key identifies a single operation, so set it for the calls of that operation only.
+	pub idempotency_key: Option<HeaderValue>,
+	/// Only send the calls to these hosts, see [`RequestOptions::allowed_hosts`].
+	pub allowed_hosts: Option<HashSet<String>>,
+	/// Only send the calls over connections reserved for this key, see [`RequestOptions::pool_key`].
+	pub pool_key: Option<String>,
+}
+
 /// JSON-RPC HTTP Client that provides functionality to perform method calls and notifications.
 #[derive(Debug, Clone)]
 pub struct HttpClient<S = HttpBackend> {
@@ -271,6 +742,12 @@ pub struct HttpClient<S = HttpBackend> {
 	id_manager: Arc<RequestIdManager>,
 	/// Ignore id in response.
 	ignore_resp_id: bool,
+	/// How the ids of a batch response are matched against the request.
+	batch_id_policy: BatchIdPolicy,
+	/// Per-method limits.
+	method_limits: Arc<MethodLimits>,
+	/// Transport options of each call.
+	call_options: CallOptions,
 }
 
 impl<S> HttpClient<S> {
@@ -278,6 +755,86 @@ impl<S> HttpClient<S> {
 	pub fn builder() -> HttpClientBuilder {
 		HttpClientBuilder::new()
 	}
+
+	/// Create a client that adds `headers` to the headers of each call, replacing the ones with the same
+	/// name, e.g. to send a short-lived `Authorization` header.
+	///
+	/// The new client shares the transport, including its connections, with this one.
+	pub fn with_headers(&self, headers: HeaderMap) -> Self
+	where
+		S: Clone,
+	{
+		Self { call_options: CallOptions { headers, ..self.call_options.clone() }, ..self.clone() }
+	}
+
+	/// Create a client that sends each call with `options`, replacing the options and headers set with
+	/// [`HttpClient::with_headers`].
+	///
+	/// The new client shares the transport, including its connections, with this one.
+	pub fn with_call_options(&self, options: CallOptions) -> Self
+	where
+		S: Clone,
+	{
+		Self { call_options: options, ..self.clone() }
+	}
+
+	/// The normalized target that calls are sent to, see [`HttpTransportClient::target`].
+	pub fn target(&self) -> &str {
+		self.transport.target()
+	}
+
+	/// Whether calls are sent over TLS, see [`HttpTransportClient::is_https`].
+	pub fn is_https(&self) -> bool {
+		self.transport.is_https()
+	}
+
+	/// Counters of the requests sent by this client, see [`HttpTransportClient::metrics`].
+	pub fn metrics(&self) -> TransportMetrics {
+		self.transport.metrics()
+	}
+
+	/// How often connections were reused, see [`HttpTransportClient::reuse_stats`].
+	pub fn reuse_stats(&self) -> ReuseStats {
+		self.transport.reuse_stats()
+	}
+
+	/// Transport options and timeout of a call with `limits`.
+	fn call_limits(&self, limits: Limits) -> (RequestOptions, Duration) {
+		let options = RequestOptions {
+			max_request_size: limits.max_request_size,
+			max_response_size: limits.max_response_size,
+			headers: self.call_options.headers.clone(),
+			idempotency_key: self.call_options.idempotency_key.clone(),
+			allowed_hosts: self.call_options.allowed_hosts.clone(),
+			pool_key: self.call_options.pool_key.clone(),
+			..Default::default()
+		};
+		(options, limits.timeout.unwrap_or(self.request_timeout))
+	}
+}
+
+impl<B, S> HttpClient<S>
+where
+	S: Service<hyper::Request<Body>, Response = hyper::Response<B>, Error = TransportError> + Clone,
+	B: HttpBody + Send + 'static,
+	B::Data: Send,
+	B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+	/// Create a client for another target with the same settings and middleware, see
+	/// [`HttpTransportClient::with_target`].
+	///
+	/// The new client shares the request ids with this one, such that no id is sent twice over a connection shared
+	/// with [`HttpClientBuilder::allow_connection_reuse`]. Only clients built with
+	/// [`HttpClientBuilder::build_retargetable`] can create clients for other targets.
+	pub fn with_target(&self, target: impl AsRef<str>) -> Result<Self, Error> {
+		let transport = self.transport.with_target(target).map_err(|e| Error::Transport(e.into()))?;
+		Ok(Self { transport, ..self.clone() })
+	}
+
+	/// Probe the max request body size of the server, see [`HttpTransportClient::probe_request_size`].
+	pub async fn probe_request_size(&self) -> Result<u32, Error> {
+		self.transport.probe_request_size().await.map_err(|e| Error::Transport(e.into()))
+	}
 }
 
 #[async_trait]
@@ -298,9 +855,10 @@ where
 		let notif =
 			serde_json::to_string(&NotificationSer::borrowed(&method, params.as_deref())).map_err(Error::ParseError)?;
 
-		let fut = self.transport.send(notif);
+		let (options, timeout) = self.call_limits(self.method_limits.resolve(method));
+		let fut = self.transport.send_with_options(notif, &options);
 
-		match tokio::time::timeout(self.request_timeout, fut).await {
+		match executor::timeout(self.transport.executor(), timeout, fut).await {
 			Ok(Ok(ok)) => Ok(ok),
 			Err(_) => Err(Error::RequestTimeout),
 			Ok(Err(e)) => Err(Error::Transport(e.into())),
@@ -322,8 +880,9 @@ where
 		let request = RequestSer::borrowed(&id, &method, params.as_deref());
 		let raw = serde_json::to_string(&request).map_err(Error::ParseError)?;
 
-		let fut = self.transport.send_and_read_body(raw);
-		let body = match tokio::time::timeout(self.request_timeout, fut).await {
+		let (options, timeout) = self.call_limits(self.method_limits.resolve(method));
+		let fut = self.transport.send_and_read_body_with_options(raw, &options);
+		let body = match executor::timeout(self.transport.executor(), timeout, fut).await {
 			Ok(Ok(body)) => body,
 			Err(_e) => {
 				return Err(Error::RequestTimeout);
@@ -358,6 +917,7 @@ where
 		R: DeserializeOwned + fmt::Debug + 'a,
 	{
 		let batch = batch.build()?;
+		let batch_len = batch.len();
 		let guard = self.id_manager.next_request_id()?;
 		let id_range = generate_batch_id_range(&guard, batch.len() as u64)?;
 
@@ -372,9 +932,11 @@ where
 			});
 		}
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?);
+		let (options, timeout) = self.call_limits(self.method_limits.default_limits());
+		let batch_request = serde_json::to_string(&batch_request).map_err(Error::ParseError)?;
+		let fut = self.transport.send_and_read_body_with_options(batch_request, &options);
 
-		let body = match tokio::time::timeout(self.request_timeout, fut).await {
+		let body = match executor::timeout(self.transport.executor(), timeout, fut).await {
 			Ok(Ok(body)) => body,
 			Err(_e) => return Err(Error::RequestTimeout),
 			Ok(Err(e)) => return Err(Error::Transport(e.into())),
@@ -382,17 +944,33 @@ where
 
 		let json_rps: Vec<Response<&JsonRawValue>> = serde_json::from_slice(&body).map_err(Error::ParseError)?;
 
-		let mut responses = Vec::with_capacity(json_rps.len());
+		let strict = self.batch_id_policy == BatchIdPolicy::StrictIds;
+		let mut responses: Vec<Option<Result<R, ErrorObject>>> = Vec::with_capacity(batch_len);
 		let mut successful_calls = 0;
 		let mut failed_calls = 0;
 
-		for _ in 0..json_rps.len() {
-			responses.push(Err(ErrorObject::borrowed(0, "", None)));
+		for _ in 0..batch_len {
+			responses.push(None);
 		}
 
 		for rp in json_rps {
 			let id = rp.id.try_parse_inner_as_number()?;
 
+			let maybe_elem = id
+				.checked_sub(id_range.start)
+				.and_then(|p| p.try_into().ok())
+				.and_then(|p: usize| responses.get_mut(p));
+
+			let elem = match maybe_elem {
+				Some(elem) if elem.is_none() => elem,
+				Some(_) if strict => return Err(InvalidRequestId::Occupied(id.to_string()).into()),
+				None if strict => return Err(InvalidRequestId::NotPendingRequest(id.to_string()).into()),
+				_ => {
+					tracing::debug!("Ignoring unexpected or duplicated batch response with ID={}", id);
+					continue;
+				}
+			};
+
 			let res = match ResponseSuccess::try_from(rp) {
 				Ok(r) => {
 					let result = serde_json::from_str(r.result.get())?;
@@ -405,19 +983,22 @@ where
 				}
 			};
 
-			let maybe_elem = id
-				.checked_sub(id_range.start)
-				.and_then(|p| p.try_into().ok())
-				.and_then(|p: usize| responses.get_mut(p));
+			*elem = Some(res);
+		}
 
-			if let Some(elem) = maybe_elem {
-				*elem = res;
-			} else {
-				return Err(InvalidRequestId::NotPendingRequest(id.to_string()).into());
+		let mut batch_responses = Vec::with_capacity(batch_len);
+		for (res, id) in responses.into_iter().zip(id_range) {
+			match res {
+				Some(res) => batch_responses.push(res),
+				None if strict => return Err(Error::Custom(format!("Missing batch response for request ID={}", id))),
+				None => {
+					failed_calls += 1;
+					batch_responses.push(Err(ErrorObject::owned(INTERNAL_ERROR_CODE, "Missing response", None::<()>)));
+				}
 			}
 		}
 
-		Ok(BatchResponse::new(successful_calls, responses, failed_calls))
+		Ok(BatchResponse::new(successful_calls, batch_responses, failed_calls))
 	}
 }
 
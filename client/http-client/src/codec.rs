@@ -0,0 +1,101 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! JSON decoding backends for [`HttpTransportClient::send_and_parse_with`](crate::transport::HttpTransportClient::send_and_parse_with).
+//!
+//! The transport itself only deals with bytes, the codec is only used to deserialize the response body.
+//! [`SerdeJson`] is always available and [`SimdJson`] requires the `simd-json` feature.
+
+use std::error::Error as StdError;
+
+use serde::de::DeserializeOwned;
+
+/// JSON decoding backend.
+pub trait JsonCodec {
+	/// Error returned when the bytes couldn't be decoded.
+	type Error: StdError + Send + Sync + 'static;
+
+	/// Whether [`JsonCodec::decode`] modifies the buffer, in which case the transport decodes a copy of the body
+	/// to keep the original for error messages.
+	const DECODES_IN_PLACE: bool = true;
+
+	/// Decode `T` from `bytes`.
+	///
+	/// The codec is allowed to modify the buffer in place if [`JsonCodec::DECODES_IN_PLACE`] is set.
+	fn decode<T: DeserializeOwned>(bytes: &mut [u8]) -> Result<T, Self::Error>;
+}
+
+/// Decode JSON with [`serde_json`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SerdeJson;
+
+impl JsonCodec for SerdeJson {
+	type Error = serde_json::Error;
+
+	const DECODES_IN_PLACE: bool = false;
+
+	fn decode<T: DeserializeOwned>(bytes: &mut [u8]) -> Result<T, Self::Error> {
+		serde_json::from_slice(bytes)
+	}
+}
+
+/// Decode JSON with [`simd_json`], which is considerably faster for large responses on CPUs with SIMD support.
+#[cfg(feature = "simd-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "simd-json")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimdJson;
+
+#[cfg(feature = "simd-json")]
+impl JsonCodec for SimdJson {
+	type Error = simd_json::Error;
+
+	fn decode<T: DeserializeOwned>(bytes: &mut [u8]) -> Result<T, Self::Error> {
+		simd_json::serde::from_slice(bytes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RESPONSE: &str = r#"{"jsonrpc":"2.0","result":{"numbers":[1,2,3],"text":"hello"},"id":0}"#;
+
+	#[test]
+	fn serde_json_decodes() {
+		let value: serde_json::Value = SerdeJson::decode(&mut RESPONSE.as_bytes().to_vec()).unwrap();
+		assert_eq!(value["result"]["numbers"][2], 3);
+		assert!(SerdeJson::decode::<u64>(&mut b"{}".to_vec()).is_err());
+	}
+
+	#[cfg(feature = "simd-json")]
+	#[test]
+	fn simd_json_decodes_like_serde_json() {
+		let simd: serde_json::Value = SimdJson::decode(&mut RESPONSE.as_bytes().to_vec()).unwrap();
+		let serde: serde_json::Value = SerdeJson::decode(&mut RESPONSE.as_bytes().to_vec()).unwrap();
+		assert_eq!(simd, serde);
+		assert!(SimdJson::decode::<u64>(&mut b"{}".to_vec()).is_err());
+	}
+}
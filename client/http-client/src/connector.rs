@@ -0,0 +1,217 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Connectors provided by the user, see
+//! [`HttpTransportClientConfig::connector`](crate::transport::HttpTransportClientConfig::connector).
+//!
+//! By default the transport connects with hyper's [`HttpConnector`](hyper::client::HttpConnector). A
+//! [`CustomConnector`] replaces it for `http` and `https` targets, e.g. to resolve hosts with DNS over HTTPS or
+//! to bind connections to a source address. For `https` targets, TLS is set up on top of the connections it makes,
+//! so it must also connect to `https` URIs, e.g. with `HttpConnector::enforce_http(false)`.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::{Connected, Connection};
+use hyper::Uri;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tower::{Service, ServiceExt};
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+type Connecting = Pin<Box<dyn Future<Output = Result<CustomStream, BoxError>> + Send>>;
+
+/// Connector of any type that implements hyper's connector interface, i.e. a `Service<Uri>` that yields
+/// connections.
+#[derive(Clone)]
+pub struct CustomConnector(Arc<dyn Fn(Uri) -> Connecting + Send + Sync>);
+
+impl CustomConnector {
+	/// Connect with `connector`, which is cloned for each connection.
+	pub fn new<C>(connector: C) -> Self
+	where
+		C: Service<Uri> + Clone + Send + Sync + 'static,
+		C::Response: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
+		C::Error: Into<BoxError>,
+		C::Future: Send + 'static,
+	{
+		Self(Arc::new(move |uri| {
+			let connect = connector.clone().oneshot(uri);
+			Box::pin(async move {
+				let stream = connect.await.map_err(Into::into)?;
+				Ok(CustomStream(Box::new(stream), None))
+			})
+		}))
+	}
+
+	/// Count the requests sent over each connection made by this connector, see [`ConnectionRequests`].
+	#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+	pub(crate) fn count_requests(self) -> Self {
+		Self(Arc::new(move |uri| {
+			let connect = (self.0)(uri);
+			Box::pin(async move {
+				let CustomStream(stream, _) = connect.await?;
+				Ok(CustomStream(stream, Some(ConnectionRequests::default())))
+			})
+		}))
+	}
+}
+
+/// Number of requests sent over a connection, which hyper adds to each of its responses.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionRequests(pub(crate) Arc<AtomicU32>);
+
+impl fmt::Debug for CustomConnector {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CustomConnector").finish_non_exhaustive()
+	}
+}
+
+impl Service<Uri> for CustomConnector {
+	type Response = CustomStream;
+	type Error = BoxError;
+	type Future = Connecting;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, uri: Uri) -> Self::Future {
+		(self.0)(uri)
+	}
+}
+
+trait Io: AsyncRead + AsyncWrite + Connection + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Connection + Unpin + Send> Io for T {}
+
+/// Connection made by a [`CustomConnector`].
+pub struct CustomStream(Box<dyn Io>, Option<ConnectionRequests>);
+
+impl fmt::Debug for CustomStream {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CustomStream").finish_non_exhaustive()
+	}
+}
+
+impl Connection for CustomStream {
+	fn connected(&self) -> Connected {
+		let connected = self.0.connected();
+		match &self.1 {
+			Some(requests) => connected.extra(requests.clone()),
+			None => connected,
+		}
+	}
+}
+
+impl AsyncRead for CustomStream {
+	fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+		Pin::new(&mut self.0).poll_read(cx, buf)
+	}
+}
+
+impl AsyncWrite for CustomStream {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+		Pin::new(&mut self.0).poll_write(cx, buf)
+	}
+
+	fn poll_write_vectored(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		bufs: &[std::io::IoSlice<'_>],
+	) -> Poll<std::io::Result<usize>> {
+		Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		self.0.is_write_vectored()
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		Pin::new(&mut self.0).poll_flush(cx)
+	}
+
+	fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		Pin::new(&mut self.0).poll_shutdown(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transport::{AddressFamily, Error, HttpTransportClient, HttpTransportClientConfig};
+	use hyper::client::HttpConnector;
+	use jsonrpsee_test_utils::helpers::http_server_with_hardcoded_response;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[tokio::test]
+	async fn custom_connector_is_used() {
+		let connections = Arc::new(AtomicUsize::new(0));
+		let counted = connections.clone();
+		// Resolves every host to the loopback address.
+		let connector = tower::service_fn(move |uri: Uri| {
+			counted.fetch_add(1, Ordering::SeqCst);
+			let port = uri.port_u16().unwrap();
+			let uri = format!("http://127.0.0.1:{port}").parse().unwrap();
+			HttpConnector::new().oneshot(uri)
+		});
+
+		let addr = http_server_with_hardcoded_response("[]".into()).await;
+		let target = format!("http://example.invalid:{}", addr.port());
+		let config =
+			HttpTransportClientConfig { connector: Some(CustomConnector::new(connector)), ..Default::default() };
+		let client = HttpTransportClient::new(&target, config, tower::ServiceBuilder::new()).unwrap();
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[]");
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[]");
+		assert_eq!(connections.load(Ordering::SeqCst), 1);
+	}
+
+	#[test]
+	fn settings_of_hyper_connector_are_rejected() {
+		let connector = CustomConnector::new(HttpConnector::new());
+		let new = |config| HttpTransportClient::new("http://localhost", config, tower::ServiceBuilder::new());
+
+		let config = HttpTransportClientConfig {
+			connector: Some(connector.clone()),
+			address_family: AddressFamily::V4Only,
+			..Default::default()
+		};
+		let err = new(config).unwrap_err();
+		assert!(matches!(err, Error::UnsupportedWithConnector { setting: "address_family" }), "{err:?}");
+
+		let config =
+			HttpTransportClientConfig { connector: Some(connector.clone()), tcp_nodelay: true, ..Default::default() };
+		let err = new(config).unwrap_err();
+		assert!(matches!(err, Error::UnsupportedWithConnector { .. }), "{err:?}");
+
+		assert!(new(HttpTransportClientConfig { connector: Some(connector), ..Default::default() }).is_ok());
+	}
+}
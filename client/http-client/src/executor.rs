@@ -0,0 +1,204 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Abstraction over the async runtime used by the HTTP client.
+//!
+//! The client spawns background tasks (hyper's connection tasks and the keep-alive ping) and
+//! waits for timeouts through an [`Executor`], which defaults to `TokioExecutor`, or to `WasmExecutor` on
+//! `wasm32` targets.
+//!
+//! Note that the default connectors still perform their socket I/O through `tokio`, so a custom
+//! executor must be driven from within a `tokio` runtime as long as those connectors are used.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{select, Either};
+
+/// Boxed future that can be sent between threads.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// Runtime used to spawn background tasks and to wait for timeouts.
+pub trait Executor: fmt::Debug + Send + Sync + 'static {
+	/// Spawn a future that runs to completion in the background.
+	fn spawn(&self, future: BoxFuture<()>);
+
+	/// Create a future that completes once `duration` has elapsed.
+	fn sleep(&self, duration: Duration) -> BoxFuture<()>;
+
+	/// Run blocking work, such as parsing a large response, without blocking the async tasks.
+	///
+	/// The default implementation runs `f` on a new thread, or in place on `wasm32` targets, which have no
+	/// threads.
+	fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+		#[cfg(not(target_arch = "wasm32"))]
+		std::thread::spawn(f);
+		#[cfg(target_arch = "wasm32")]
+		f();
+	}
+}
+
+/// Executor used if none is configured.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type DefaultExecutor = TokioExecutor;
+#[cfg(target_arch = "wasm32")]
+pub(crate) type DefaultExecutor = WasmExecutor;
+
+/// [`Executor`] backed by the `tokio` runtime.
+///
+/// ## Panics
+///
+/// Spawning panics if called outside of `tokio` runtime context.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioExecutor;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Executor for TokioExecutor {
+	fn spawn(&self, future: BoxFuture<()>) {
+		tokio::spawn(future);
+	}
+
+	fn sleep(&self, duration: Duration) -> BoxFuture<()> {
+		Box::pin(tokio::time::sleep(duration))
+	}
+
+	fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+		tokio::task::spawn_blocking(f);
+	}
+}
+
+/// [`Executor`] for `wasm32` targets, which runs the tasks on the event loop of the browser or worker.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasmExecutor;
+
+#[cfg(target_arch = "wasm32")]
+impl Executor for WasmExecutor {
+	fn spawn(&self, future: BoxFuture<()>) {
+		wasm_bindgen_futures::spawn_local(future);
+	}
+
+	fn sleep(&self, duration: Duration) -> BoxFuture<()> {
+		let millis = u32::try_from(duration.as_millis()).unwrap_or(u32::MAX);
+		// The timer isn't `Send`, but `wasm32` targets are single-threaded.
+		Box::pin(send_wrapper::SendWrapper::new(gloo_timers::future::TimeoutFuture::new(millis)))
+	}
+}
+
+/// Error returned by [`timeout`] when the future didn't complete in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Elapsed;
+
+/// Wait for `future` to complete for at most `duration`.
+pub(crate) async fn timeout<F: Future>(
+	executor: &dyn Executor,
+	duration: Duration,
+	future: F,
+) -> Result<F::Output, Elapsed> {
+	futures_util::pin_mut!(future);
+
+	match select(future, executor.sleep(duration)).await {
+		Either::Left((output, _)) => Ok(output),
+		Either::Right(_) => Err(Elapsed),
+	}
+}
+
+/// Adapter to let hyper spawn its connection tasks on an [`Executor`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub(crate) struct HyperExecutor(pub(crate) Arc<dyn Executor>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<F> hyper::rt::Executor<F> for HyperExecutor
+where
+	F: Future + Send + 'static,
+	F::Output: Send,
+{
+	fn execute(&self, future: F) {
+		self.0.spawn(Box::pin(async move {
+			future.await;
+		}));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::HttpClientBuilder;
+	use jsonrpsee_core::client::ClientT;
+	use jsonrpsee_core::rpc_params;
+	use jsonrpsee_test_utils::helpers::{http_server_with_hardcoded_response, ok_response};
+	use jsonrpsee_test_utils::mocks::Id;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[derive(Debug, Default)]
+	struct CountingExecutor {
+		spawned: Arc<AtomicUsize>,
+		slept: Arc<AtomicUsize>,
+	}
+
+	impl Executor for CountingExecutor {
+		fn spawn(&self, future: BoxFuture<()>) {
+			self.spawned.fetch_add(1, Ordering::SeqCst);
+			TokioExecutor.spawn(future);
+		}
+
+		fn sleep(&self, duration: Duration) -> BoxFuture<()> {
+			self.slept.fetch_add(1, Ordering::SeqCst);
+			TokioExecutor.sleep(duration)
+		}
+	}
+
+	#[tokio::test]
+	async fn client_uses_custom_executor() {
+		let executor = CountingExecutor::default();
+		let spawned = executor.spawned.clone();
+		let slept = executor.slept.clone();
+
+		let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0))).await;
+		let client =
+			HttpClientBuilder::default().set_executor(executor).build(format!("http://{server_addr}")).unwrap();
+		let response: String = client.request("say_hello", rpc_params![]).await.unwrap();
+
+		assert_eq!(response, "hello");
+		assert!(spawned.load(Ordering::SeqCst) > 0);
+		assert_eq!(slept.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test]
+	async fn timeout_works() {
+		assert_eq!(timeout(&TokioExecutor, Duration::from_secs(10), async { 7 }).await, Ok(7));
+		assert_eq!(
+			timeout(&TokioExecutor, Duration::from_millis(10), futures_util::future::pending::<()>()).await,
+			Err(Elapsed)
+		);
+	}
+}
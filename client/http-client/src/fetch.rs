@@ -0,0 +1,154 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! HTTP backend for `wasm32` targets that sends requests with the `fetch` API of the browser or worker.
+//!
+//! [`FetchService`] has the same `Service<hyper::Request<Body>>` shape as the hyper backend of the transport, so
+//! `tower` layers written for the native transport also work on top of it. It mirrors the native path as far as
+//! `fetch` allows:
+//!
+//! - Headers that `fetch` forbids scripts to set, e.g. `Host`, `Connection` and `Content-Length`, are left to
+//!   the browser.
+//! - Redirects are followed by the browser, so the redirect limits of the transport don't apply.
+//! - The browser decompresses response bodies, so `Content-Encoding` and `Content-Length` are removed from the
+//!   response headers.
+//! - A response larger than the max response size fails with [`Error::ResponseTooLarge`], before reading the
+//!   body if the server sends its length.
+//! - TLS, proxies and connection pooling are up to the browser.
+//!
+//! It is the backend of the transport on `wasm32` targets, so
+//! [`HttpClientBuilder::build`](crate::HttpClientBuilder::build) and
+//! [`HttpTransportClient::new`](crate::transport::HttpTransportClient::new) use it without any change to the code.
+//! The connection settings of the transport, e.g. the pool, the proxy and the TCP settings, don't apply, and the
+//! tasks of the client run on [`WasmExecutor`](crate::executor::WasmExecutor).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use gloo_net::http::{Headers, RequestBuilder};
+use hyper::header::{self, HeaderName};
+use hyper::Body;
+use send_wrapper::SendWrapper;
+use tower::Service;
+
+use crate::transport::Error;
+
+/// Sends requests with `fetch`, see the [module documentation](self).
+#[derive(Debug, Clone, Copy)]
+pub struct FetchService {
+	max_response_size: u32,
+}
+
+impl FetchService {
+	/// Create a service that rejects responses larger than `max_response_size` bytes.
+	pub fn new(max_response_size: u32) -> Self {
+		Self { max_response_size }
+	}
+}
+
+impl Service<hyper::Request<Body>> for FetchService {
+	type Response = hyper::Response<Body>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+		// The JavaScript values aren't `Send`, but `wasm32` targets are single-threaded.
+		Box::pin(SendWrapper::new(fetch(req, self.max_response_size)))
+	}
+}
+
+async fn fetch(req: hyper::Request<Body>, max_response_size: u32) -> Result<hyper::Response<Body>, Error> {
+	let (parts, body) = req.into_parts();
+	let body = hyper::body::to_bytes(body).await.map_err(|e| Error::Http(Box::new(e)))?;
+
+	let headers = Headers::new();
+	for (name, value) in &parts.headers {
+		if is_forbidden(name) {
+			continue;
+		}
+		let value = value.to_str().map_err(|e| Error::Http(Box::new(e)))?;
+		headers.append(name.as_str(), value);
+	}
+
+	let request = RequestBuilder::new(&parts.uri.to_string())
+		.method(parts.method)
+		.headers(headers)
+		.body(js_sys::Uint8Array::from(&body[..]))
+		.map_err(fetch_error)?;
+	let response = request.send().await.map_err(fetch_error)?;
+
+	let content_length = response.headers().get(header::CONTENT_LENGTH.as_str()).and_then(|len| len.parse().ok());
+	if content_length.map_or(false, |length: u64| length > max_response_size as u64) {
+		return Err(Error::ResponseTooLarge);
+	}
+	let body = response.binary().await.map_err(fetch_error)?;
+	if body.len() > max_response_size as usize {
+		return Err(Error::ResponseTooLarge);
+	}
+
+	let mut builder = hyper::Response::builder().status(response.status());
+	for (name, value) in response.headers().entries() {
+		if name.eq_ignore_ascii_case(header::CONTENT_ENCODING.as_str())
+			|| name.eq_ignore_ascii_case(header::CONTENT_LENGTH.as_str())
+		{
+			continue;
+		}
+		builder = builder.header(name, value);
+	}
+	builder.body(Body::from(body)).map_err(|e| Error::Http(Box::new(e)))
+}
+
+/// Whether `fetch` refuses to send the header, see <https://fetch.spec.whatwg.org/#forbidden-request-header>.
+fn is_forbidden(name: &HeaderName) -> bool {
+	let name = name.as_str();
+	name.starts_with("proxy-")
+		|| name.starts_with("sec-")
+		|| matches!(
+			name,
+			"accept-charset"
+				| "accept-encoding"
+				| "access-control-request-headers"
+				| "access-control-request-method"
+				| "connection"
+				| "content-length"
+				| "cookie" | "date"
+				| "dnt" | "expect"
+				| "host" | "keep-alive"
+				| "origin" | "referer"
+				| "te" | "trailer"
+				| "transfer-encoding"
+				| "upgrade" | "via"
+		)
+}
+
+fn fetch_error(err: gloo_net::Error) -> Error {
+	Error::Http(err.to_string().into())
+}
@@ -0,0 +1,410 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Capture HTTP exchanges in the [HAR](http://www.softwareishard.com/blog/har-12-spec/) format for offline analysis.
+//!
+//! [`HarLayer`] wraps the HTTP backend and, next to actually sending each request, appends it as a HAR 1.2
+//! entry to a file, one JSON object per line. Responses are included unless disabled. Use [`har_log`]
+//! to assemble the entries into a HAR file that can be imported into browser devtools.
+//!
+//! Response bodies are passed on to the transport while they are captured, so the limits of the transport
+//! still apply. An entry with a response is written once the transport has read the body, or stopped reading
+//! it, and only the first [`HarLayer::max_body_size`] bytes of the body are included.
+//!
+//! ```no_run
+//! use jsonrpsee_http_client::har::{har_log, HarLayer};
+//! use jsonrpsee_http_client::HttpClientBuilder;
+//!
+//! let har = HarLayer::new("requests.jsonl").unwrap().redact_header("x-api-key");
+//! let middleware = tower::ServiceBuilder::new().layer(har);
+//! let client = HttpClientBuilder::default().set_http_middleware(middleware).build("http://localhost:9933").unwrap();
+//!
+//! // Later:
+//! let log = har_log("requests.jsonl").unwrap();
+//! std::fs::write("requests.har", log.to_string()).unwrap();
+//! ```
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hyper::http::HeaderMap;
+use hyper::Body;
+use serde_json::{json, Value};
+use tower::{Layer, Service};
+
+use crate::tee::tee;
+use crate::transport::Error;
+
+/// Replacement of the values of redacted headers.
+const REDACTED: &str = "[redacted]";
+
+/// Default of [`HarLayer::max_body_size`].
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that writes every request of the wrapped service as a HAR entry to a file.
+///
+/// Header values marked as sensitive (see [`HeaderValue::set_sensitive`](hyper::http::HeaderValue::set_sensitive))
+/// and the values of headers configured with [`HarLayer::redact_header`] are replaced by `[redacted]`, in
+/// requests as well as in responses.
+#[derive(Debug, Clone)]
+pub struct HarLayer {
+	file: Arc<Mutex<File>>,
+	redacted: Arc<HashSet<String>>,
+	include_responses: bool,
+	max_body_size: usize,
+}
+
+impl HarLayer {
+	/// Write entries to the file at `path`, which is created or truncated.
+	pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+		Ok(Self {
+			file: Arc::new(Mutex::new(File::create(path)?)),
+			redacted: Default::default(),
+			include_responses: true,
+			max_body_size: DEFAULT_MAX_BODY_SIZE,
+		})
+	}
+
+	/// Redact the values of the header `name`, which is matched case-insensitively.
+	pub fn redact_header(mut self, name: impl AsRef<str>) -> Self {
+		Arc::make_mut(&mut self.redacted).insert(name.as_ref().to_ascii_lowercase());
+		self
+	}
+
+	/// Include the response in the entries (default is true).
+	///
+	/// Without responses, the response of each entry only contains the status code.
+	pub fn include_responses(mut self, include: bool) -> Self {
+		self.include_responses = include;
+		self
+	}
+
+	/// Include at most this many bytes of each response body (default is 1 MiB).
+	///
+	/// The content of a truncated body has a comment with its full size.
+	pub fn max_body_size(mut self, max: usize) -> Self {
+		self.max_body_size = max;
+		self
+	}
+}
+
+impl<S> Layer<S> for HarLayer {
+	type Service = HarRecorder<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		HarRecorder { inner, config: self.clone() }
+	}
+}
+
+/// Service that writes every request of the wrapped service as a HAR entry to a file, see [`HarLayer`].
+#[derive(Debug, Clone)]
+pub struct HarRecorder<S> {
+	inner: S,
+	config: HarLayer,
+}
+
+impl<S> Service<hyper::Request<Body>> for HarRecorder<S>
+where
+	S: Service<hyper::Request<Body>, Response = hyper::Response<Body>, Error = Error> + Clone + Send + 'static,
+	S::Future: Send,
+{
+	type Response = hyper::Response<Body>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(ctx)
+	}
+
+	fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+		// Use the service that was driven to readiness and leave the clone in its place.
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let config = self.config.clone();
+
+		Box::pin(async move {
+			let started = SystemTime::now();
+			let (parts, body) = req.into_parts();
+			let request_body = hyper::body::to_bytes(body).await?;
+			let request = json!({
+				"method": parts.method.as_str(),
+				"url": parts.uri.to_string(),
+				"httpVersion": format!("{:?}", parts.version),
+				"cookies": [],
+				"headers": config.headers(&parts.headers),
+				"queryString": query_string(parts.uri.query()),
+				"postData": {
+					"mimeType": mime_type(&parts.headers),
+					"text": String::from_utf8_lossy(&request_body),
+				},
+				"headersSize": -1,
+				"bodySize": request_body.len(),
+			});
+
+			let sent = Instant::now();
+			let response = inner.call(hyper::Request::from_parts(parts, Body::from(request_body))).await?;
+			let wait = sent.elapsed();
+
+			let (parts, body) = response.into_parts();
+			let status = json!({
+				"status": parts.status.as_u16(),
+				"statusText": parts.status.canonical_reason().unwrap_or_default(),
+				"httpVersion": format!("{:?}", parts.version),
+			});
+			let entry = move |response, receive: Duration| {
+				json!({
+					"startedDateTime": iso8601(started),
+					"time": (wait + receive).as_secs_f64() * 1000.0,
+					"request": request,
+					"response": response,
+					"cache": {},
+					"timings": { "send": 0, "wait": wait.as_secs_f64() * 1000.0, "receive": receive.as_secs_f64() * 1000.0 },
+				})
+			};
+			if !config.include_responses {
+				config.write(&entry(status, Duration::ZERO))?;
+				return Ok(hyper::Response::from_parts(parts, body));
+			}
+
+			let mut response = status;
+			let location = parts.headers.get(hyper::header::LOCATION).map(|l| String::from_utf8_lossy(l.as_bytes()));
+			response["cookies"] = json!([]);
+			response["headers"] = config.headers(&parts.headers);
+			response["redirectURL"] = json!(location.unwrap_or_default());
+			response["headersSize"] = json!(-1);
+			let mime_type = mime_type(&parts.headers);
+
+			let received = Instant::now();
+			let body = tee(body, config.max_body_size, move |body| {
+				let receive = received.elapsed();
+				let mut content = json!({
+					"size": body.size,
+					"mimeType": mime_type,
+					"text": String::from_utf8_lossy(&body.bytes),
+				});
+				let mut comment = Vec::new();
+				if !body.complete {
+					comment.push(format!("Not read to the end after {} bytes", body.size));
+				}
+				if body.truncated() {
+					comment.push(format!("Truncated to {} of {} bytes", body.bytes.len(), body.size));
+				}
+				if !comment.is_empty() {
+					content["comment"] = json!(comment.join(", "));
+				}
+				response["content"] = content;
+				response["bodySize"] = json!(body.size);
+				if let Err(err) = config.write(&entry(response, receive)) {
+					tracing::warn!("Failed to write HAR entry: {err}");
+				}
+			});
+
+			Ok(hyper::Response::from_parts(parts, body))
+		})
+	}
+}
+
+impl HarLayer {
+	/// Append `entry` to the file.
+	fn write(&self, entry: &Value) -> Result<(), Error> {
+		let mut line = entry.to_string();
+		line.push('\n');
+
+		let mut file = self.file.lock().expect("lock poisoned; qed");
+		file.write_all(line.as_bytes()).and_then(|_| file.flush()).map_err(|e| Error::Http(Box::new(e)))
+	}
+
+	/// HAR representation of `headers`, with redacted values.
+	fn headers(&self, headers: &HeaderMap) -> Value {
+		let headers: Vec<_> = headers
+			.iter()
+			.map(|(name, value)| {
+				let value = if value.is_sensitive() || self.redacted.contains(name.as_str()) {
+					REDACTED.into()
+				} else {
+					String::from_utf8_lossy(value.as_bytes())
+				};
+				json!({ "name": name.as_str(), "value": value })
+			})
+			.collect();
+		Value::Array(headers)
+	}
+}
+
+/// Assemble the entries written by [`HarLayer`] to the file at `path` into a HAR log.
+pub fn har_log(path: impl AsRef<Path>) -> std::io::Result<Value> {
+	let mut entries = Vec::new();
+	for line in BufReader::new(File::open(path)?).lines() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		entries.push(serde_json::from_str::<Value>(&line)?);
+	}
+
+	Ok(json!({
+		"log": {
+			"version": "1.2",
+			"creator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+			"entries": entries,
+		}
+	}))
+}
+
+fn mime_type(headers: &HeaderMap) -> String {
+	headers
+		.get(hyper::header::CONTENT_TYPE)
+		.map(|c| String::from_utf8_lossy(c.as_bytes()).into_owned())
+		.unwrap_or_default()
+}
+
+fn query_string(query: Option<&str>) -> Value {
+	let params: Vec<_> = url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+		.map(|(name, value)| json!({ "name": name, "value": value }))
+		.collect();
+	Value::Array(params)
+}
+
+/// Format `time` as an ISO 8601 date in UTC with millisecond precision.
+fn iso8601(time: SystemTime) -> String {
+	let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+	let secs = since_epoch.as_secs();
+	let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+
+	// Civil from days, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let day_of_era = z.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+	format!(
+		"{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+		secs_of_day / 3600,
+		secs_of_day / 60 % 60,
+		secs_of_day % 60,
+		since_epoch.subsec_millis()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HeaderValue, HttpClientBuilder};
+	use jsonrpsee_core::client::ClientT;
+	use jsonrpsee_core::rpc_params;
+	use jsonrpsee_test_utils::helpers::{http_server_with_hardcoded_response, ok_response};
+	use jsonrpsee_test_utils::mocks::Id;
+
+	#[test]
+	fn iso8601_works() {
+		assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+		let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+		assert_eq!(iso8601(time), "2024-02-29T12:34:56.789Z");
+	}
+
+	#[tokio::test]
+	async fn requests_are_written_as_har_entries() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-har-{}.jsonl", std::process::id()));
+
+		let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0))).await;
+		let har = HarLayer::new(&path).unwrap().redact_header("X-Api-Key");
+		let mut headers = HeaderMap::new();
+		let mut token = HeaderValue::from_static("Bearer secret");
+		token.set_sensitive(true);
+		headers.insert("authorization", token);
+		headers.insert("x-api-key", HeaderValue::from_static("key"));
+		headers.insert("x-tenant", HeaderValue::from_static("a"));
+		let client = HttpClientBuilder::default()
+			.set_headers(headers)
+			.set_http_middleware(tower::ServiceBuilder::new().layer(har))
+			.build(format!("http://{server_addr}"))
+			.unwrap();
+		let response: String = client.request("say_hello", rpc_params![]).await.unwrap();
+		assert_eq!(response, "hello");
+
+		let log = har_log(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let entries = log["log"]["entries"].as_array().unwrap();
+		assert_eq!(entries.len(), 1);
+
+		let request = &entries[0]["request"];
+		assert_eq!(request["method"], "POST");
+		assert_eq!(request["url"], format!("http://{server_addr}/"));
+		assert_eq!(request["postData"]["text"], r#"{"jsonrpc":"2.0","id":0,"method":"say_hello"}"#);
+		let header = |name: &str| {
+			request["headers"].as_array().unwrap().iter().find(|h| h["name"] == name).unwrap()["value"].clone()
+		};
+		assert_eq!(header("authorization"), REDACTED);
+		assert_eq!(header("x-api-key"), REDACTED);
+		assert_eq!(header("x-tenant"), "a");
+
+		let response = &entries[0]["response"];
+		assert_eq!(response["status"], 200);
+		assert_eq!(response["content"]["text"], ok_response("hello".into(), Id::Num(0)));
+	}
+
+	#[tokio::test]
+	async fn response_bodies_are_streamed_and_truncated() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-har-truncated-{}.jsonl", std::process::id()));
+
+		let body = ok_response("hello".repeat(100).into(), Id::Num(0));
+		let server_addr = http_server_with_hardcoded_response(body.clone()).await;
+		let har = HarLayer::new(&path).unwrap().max_body_size(16);
+		let client = |max_response_size| {
+			HttpClientBuilder::default()
+				.max_response_size(max_response_size)
+				.set_http_middleware(tower::ServiceBuilder::new().layer(har.clone()))
+				.build(format!("http://{server_addr}"))
+				.unwrap()
+		};
+		let response: String = client(1024).request("say_hello", rpc_params![]).await.unwrap();
+		assert_eq!(response, "hello".repeat(100));
+		// The transport still enforces its max response size.
+		assert!(client(100).request::<String, _>("say_hello", rpc_params![]).await.is_err());
+
+		let log = har_log(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let entries = log["log"]["entries"].as_array().unwrap();
+		let content = &entries[0]["response"]["content"];
+		assert_eq!(content["text"], body[..16]);
+		assert_eq!(content["size"], body.len());
+		assert_eq!(content["comment"], format!("Truncated to 16 of {} bytes", body.len()));
+		let content = &entries[1]["response"]["content"];
+		assert_eq!(content["comment"], "Not read to the end after 0 bytes");
+	}
+}
@@ -31,20 +31,58 @@
 //! It is tightly-coupled to [`tokio`](https://docs.rs/tokio) because [`hyper`](https://docs.rs/hyper) is used as transport client,
 //! which is not compatible with other async runtimes such as
 //! [`async-std`](https://docs.rs/async-std/), [`smol`](https://docs.rs/smol) and similar.
+//!
+//! On `wasm32` targets the requests are sent with the `fetch` API instead, see the `fetch` module.
 
 #![warn(missing_docs, missing_debug_implementations, missing_copy_implementations, unreachable_pub)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
-#![feature(let_chains)]
 
 mod client;
 
 /// HTTP transport.
 pub mod transport;
 
+#[cfg(target_arch = "wasm32")]
+pub mod fetch;
+
+pub mod auth;
+pub mod codec;
+pub mod connector;
+pub mod executor;
+pub mod limits;
+pub mod observer;
+pub mod progress;
+pub mod proxy;
+
+#[cfg(feature = "har")]
+#[cfg_attr(docsrs, doc(cfg(feature = "har")))]
+pub mod har;
+
+#[cfg(feature = "otel")]
+#[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+pub mod otel;
+
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod mock;
+
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod record;
+
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod wiretap;
+
+#[cfg(any(feature = "har", feature = "test-utils"))]
+mod tee;
+
 #[cfg(test)]
 mod tests;
 
-pub use client::{HttpClient, HttpClientBuilder};
+pub use client::{BatchIdPolicy, CallOptions, HttpClient, HttpClientBuilder};
 pub use hyper::http::{HeaderMap, HeaderValue};
 pub use jsonrpsee_types as types;
+#[cfg(feature = "__tls")]
+pub use rustls;
 pub use tower;
@@ -0,0 +1,114 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Per-method size and time limits.
+//!
+//! A [`MethodLimits`] policy is registered with
+//! [`HttpClientBuilder::method_limits`](crate::HttpClientBuilder::method_limits). The client looks up the
+//! limits of each call by method name and passes the size limits to the transport as
+//! [`RequestOptions`](crate::transport::RequestOptions).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Limits of a single call. Limits that are `None` fall back to the next level, see [`MethodLimits`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+	/// Max request body size.
+	pub max_request_size: Option<u32>,
+	/// Max response body size.
+	pub max_response_size: Option<u32>,
+	/// Request timeout.
+	pub timeout: Option<Duration>,
+}
+
+impl Limits {
+	/// Fill the limits that aren't set from `fallback`.
+	pub fn or(self, fallback: Limits) -> Limits {
+		Limits {
+			max_request_size: self.max_request_size.or(fallback.max_request_size),
+			max_response_size: self.max_response_size.or(fallback.max_response_size),
+			timeout: self.timeout.or(fallback.timeout),
+		}
+	}
+}
+
+/// Limits by JSON-RPC method name.
+///
+/// Each limit is resolved separately: a limit set for the method is used if there is one, otherwise the
+/// default limit of the policy and finally the limit configured on the client. Batches may mix methods
+/// and therefore only use the default limits.
+#[derive(Debug, Clone, Default)]
+pub struct MethodLimits {
+	default: Limits,
+	methods: HashMap<String, Limits>,
+}
+
+impl MethodLimits {
+	/// Create a policy with limits for methods that have no entry of their own.
+	pub fn new(default: Limits) -> Self {
+		Self { default, methods: HashMap::new() }
+	}
+
+	/// Set the limits of `method`, replacing earlier limits of the method.
+	pub fn with_method(mut self, method: impl Into<String>, limits: Limits) -> Self {
+		self.methods.insert(method.into(), limits);
+		self
+	}
+
+	/// Default limits of the policy.
+	pub fn default_limits(&self) -> Limits {
+		self.default
+	}
+
+	/// Limits of `method`, with unset limits taken from the default limits.
+	pub fn resolve(&self, method: &str) -> Limits {
+		match self.methods.get(method) {
+			Some(limits) => limits.or(self.default),
+			None => self.default,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn limits_fall_back_to_default() {
+		let default =
+			Limits { max_response_size: Some(100), timeout: Some(Duration::from_secs(1)), ..Default::default() };
+		let big = Limits { max_response_size: Some(10_000), ..Default::default() };
+		let policy = MethodLimits::new(default).with_method("get_blocks", big);
+
+		assert_eq!(policy.resolve("get_block"), default);
+		assert_eq!(
+			policy.resolve("get_blocks"),
+			Limits { max_request_size: None, max_response_size: Some(10_000), timeout: Some(Duration::from_secs(1)) }
+		);
+		assert_eq!(MethodLimits::default().resolve("get_blocks"), Limits::default());
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Mock HTTP backend for tests without a server.
+//!
+//! [`MockHttpService`] answers requests with canned responses, either in the order they were queued or by
+//! request body, and remembers the requests it got. Use it as a [`Layer`] to replace the HTTP backend of a
+//! client, e.g. to drive the redirect handling of the transport:
+//!
+//! ```
+//! use jsonrpsee_http_client::mock::MockHttpService;
+//! use jsonrpsee_http_client::transport::{HttpTransportClient, HttpTransportClientConfig};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let mock = MockHttpService::new();
+//! mock.push_response(hyper::Response::builder().status(302).header("location", "/moved").body("".into()).unwrap());
+//! mock.push_response(hyper::Response::new(r#"{"jsonrpc":"2.0","result":1,"id":0}"#.into()));
+//!
+//! let middleware = tower::ServiceBuilder::new().layer(mock.clone());
+//! let config = HttpTransportClientConfig::default();
+//! let client = HttpTransportClient::new("http://localhost/rpc", config, middleware).unwrap();
+//! let body = client.send_and_read_body(r#"{"jsonrpc":"2.0","method":"m","id":0}"#.into()).await.unwrap();
+//!
+//! assert_eq!(body, br#"{"jsonrpc":"2.0","result":1,"id":0}"#);
+//! let uris: Vec<_> = mock.requests().into_iter().map(|request| request.uri.to_string()).collect();
+//! assert_eq!(uris, ["http://localhost/rpc", "http://localhost/moved"]);
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use hyper::http::{HeaderMap, Method, Uri};
+use hyper::Body;
+use tower::{Layer, Service};
+
+use crate::transport::Error;
+
+/// Handler of the requests with a given body, see [`MockHttpService::on_body`].
+type Handler = Box<dyn FnMut(&MockRequest) -> hyper::Response<Body> + Send>;
+
+/// A request received by a [`MockHttpService`].
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+	/// Method of the request.
+	pub method: Method,
+	/// URI of the request, including the redirect target for redirected requests.
+	pub uri: Uri,
+	/// Headers of the request.
+	pub headers: HeaderMap,
+	/// Body of the request, converted lossily to UTF-8.
+	pub body: String,
+}
+
+/// Service that answers requests with canned responses instead of connecting to a server.
+///
+/// A request is answered by the handler of its body if there is one, see [`MockHttpService::on_body`], and
+/// otherwise with the next queued response, see [`MockHttpService::push_response`]. A request that neither
+/// matches fails with [`Error::Http`].
+///
+/// Clones share the responses and the received requests, so a clone can be kept to queue more responses and
+/// to inspect the requests after the service was moved into a client.
+#[derive(Clone, Default)]
+pub struct MockHttpService {
+	state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+	queue: VecDeque<hyper::Response<Body>>,
+	handlers: HashMap<String, Handler>,
+	requests: Vec<MockRequest>,
+}
+
+impl fmt::Debug for MockHttpService {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let state = self.state.lock().expect("lock poisoned; qed");
+		f.debug_struct("MockHttpService")
+			.field("queued", &state.queue.len())
+			.field("handlers", &state.handlers.keys().collect::<Vec<_>>())
+			.field("requests", &state.requests.len())
+			.finish()
+	}
+}
+
+impl MockHttpService {
+	/// Create a service without any responses.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Answer the next request that no handler matches with `response`.
+	///
+	/// Responses are served in the order they were queued, each one once.
+	pub fn push_response(&self, response: hyper::Response<Body>) -> &Self {
+		self.state().queue.push_back(response);
+		self
+	}
+
+	/// Answer every request with the given `body` with the response returned by `handler`, replacing the
+	/// previous handler of the body.
+	pub fn on_body(
+		&self,
+		body: impl Into<String>,
+		handler: impl FnMut(&MockRequest) -> hyper::Response<Body> + Send + 'static,
+	) -> &Self {
+		self.state().handlers.insert(body.into(), Box::new(handler));
+		self
+	}
+
+	/// Requests received so far, in the order they were received.
+	pub fn requests(&self) -> Vec<MockRequest> {
+		self.state().requests.clone()
+	}
+
+	fn state(&self) -> std::sync::MutexGuard<'_, State> {
+		self.state.lock().expect("lock poisoned; qed")
+	}
+
+	fn respond(&self, request: MockRequest) -> Result<hyper::Response<Body>, Error> {
+		let mut state = self.state();
+		let response = match state.handlers.get_mut(&request.body) {
+			Some(handler) => Some(handler(&request)),
+			None => state.queue.pop_front(),
+		};
+		let response =
+			response.ok_or_else(|| Error::Http(format!("No mock response for request: {}", request.body).into()));
+		state.requests.push(request);
+		response
+	}
+}
+
+impl<S> Layer<S> for MockHttpService {
+	type Service = MockHttpService;
+
+	fn layer(&self, _inner: S) -> Self::Service {
+		self.clone()
+	}
+}
+
+impl Service<hyper::Request<Body>> for MockHttpService {
+	type Response = hyper::Response<Body>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+		let this = self.clone();
+
+		Box::pin(async move {
+			let (parts, body) = req.into_parts();
+			let body = hyper::body::to_bytes(body).await?;
+			let request = MockRequest {
+				method: parts.method,
+				uri: parts.uri,
+				headers: parts.headers,
+				body: String::from_utf8_lossy(&body).into_owned(),
+			};
+			this.respond(request)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transport::{HttpTransportClient, HttpTransportClientConfig};
+	use hyper::StatusCode;
+
+	fn client(mock: &MockHttpService, config: HttpTransportClientConfig) -> HttpTransportClient<MockHttpService> {
+		HttpTransportClient::new("http://localhost", config, tower::ServiceBuilder::new().layer(mock.clone())).unwrap()
+	}
+
+	#[tokio::test]
+	async fn handlers_take_precedence_over_queued_responses() {
+		let mock = MockHttpService::new();
+		mock.push_response(hyper::Response::new("[1]".into())).on_body("{}", |request| {
+			assert_eq!(request.method, Method::POST);
+			hyper::Response::new("[0]".into())
+		});
+		let client = client(&mock, HttpTransportClientConfig::default());
+
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[0]");
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[0]");
+		assert_eq!(client.send_and_read_body("[]".into()).await.unwrap(), b"[1]");
+		assert!(matches!(client.send_and_read_body("[]".into()).await, Err(Error::Http(_))));
+
+		let bodies: Vec<_> = mock.requests().into_iter().map(|request| request.body).collect();
+		assert_eq!(bodies, ["{}", "{}", "[]", "[]"]);
+	}
+
+	#[tokio::test]
+	async fn status_and_size_errors_are_reported() {
+		let mock = MockHttpService::new();
+		mock.push_response(hyper::Response::builder().status(StatusCode::BAD_GATEWAY).body("".into()).unwrap());
+		mock.push_response(hyper::Response::new(format!("[{}1]", "1,".repeat(64)).into()));
+		let client = client(&mock, HttpTransportClientConfig { max_response_size: 64, ..Default::default() });
+
+		let err = client.send_and_read_body("{}".into()).await.unwrap_err();
+		assert!(matches!(err, Error::RequestFailure { status_code: 502, .. }), "{err:?}");
+		let err = client.send_and_read_body("{}".into()).await.unwrap_err();
+		assert!(matches!(err, Error::ResponseTooLarge), "{err:?}");
+	}
+}
@@ -0,0 +1,99 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Callbacks to observe the HTTP transport, e.g. to export metrics.
+//!
+//! Register an [`Observer`] with [`HttpClientBuilder::set_observer`](crate::HttpClientBuilder::set_observer)
+//! or in the [`HttpTransportClientConfig`](crate::transport::HttpTransportClientConfig).
+
+use crate::transport::{Error, ReuseStats};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callbacks invoked by the transport.
+///
+/// All methods have a default implementation that does nothing, such that an observer only needs to
+/// implement the events it's interested in. The callbacks are invoked on the hot path and should be cheap.
+pub trait Observer: fmt::Debug + Send + Sync + 'static {
+	/// A response of `size` bytes exceeded the response size warning threshold, but not the max response size.
+	fn on_response_size_warning(&self, size: usize, threshold: u32) {
+		let _ = (size, threshold);
+	}
+
+	/// A request is about to be retried, see [`RetryConfig`](crate::transport::RetryConfig).
+	///
+	/// Invoked after the delay before the next attempt has been computed and before waiting for it, like a
+	/// [`RetryHook`].
+	fn on_retry(&self, info: RetryInfo<'_>) {
+		let _ = info;
+	}
+
+	/// An HTTP request was sent and its response headers received, or it failed.
+	///
+	/// `stats` are the connection reuse statistics of the client including this request, see
+	/// [`HttpTransportClient::reuse_stats`](crate::transport::HttpTransportClient::reuse_stats).
+	fn on_request_sent(&self, stats: ReuseStats) {
+		let _ = stats;
+	}
+}
+
+/// A failed attempt that is retried, see [`Observer::on_retry`] and [`RetryHook`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct RetryInfo<'a> {
+	/// Number of the attempt that failed, starting at 1.
+	pub attempt: u32,
+	/// Error of the failed attempt.
+	pub error: &'a Error,
+	/// Delay before the next attempt.
+	pub delay: Duration,
+}
+
+/// Function invoked before each retry of a request, e.g. to log retries without implementing an [`Observer`].
+///
+/// Set with [`HttpClientBuilder::set_on_retry`](crate::HttpClientBuilder::set_on_retry) or in
+/// [`HttpTransportClientConfig::on_retry`](crate::transport::HttpTransportClientConfig::on_retry). It's invoked
+/// at the same point as [`Observer::on_retry`], after the observer.
+#[derive(Clone)]
+pub struct RetryHook(Arc<dyn Fn(RetryInfo<'_>) + Send + Sync>);
+
+impl RetryHook {
+	/// Create a hook from a function invoked with each retried attempt.
+	pub fn new(f: impl Fn(RetryInfo<'_>) + Send + Sync + 'static) -> Self {
+		Self(Arc::new(f))
+	}
+
+	pub(crate) fn call(&self, info: RetryInfo<'_>) {
+		(self.0)(info)
+	}
+}
+
+impl fmt::Debug for RetryHook {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RetryHook").finish_non_exhaustive()
+	}
+}
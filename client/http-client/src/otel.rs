@@ -0,0 +1,49 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Propagate the trace context of the current task, e.g. an [OpenTelemetry](https://opentelemetry.io) context,
+//! to the server.
+//!
+//! Register an [`Injector`] in the
+//! [`HttpTransportClientConfig`](crate::transport::HttpTransportClientConfig) or with
+//! [`HttpClientBuilder::set_context_injector`](crate::HttpClientBuilder::set_context_injector). It's invoked for
+//! every request, including each redirect hop, on the task that sends the request, so it can read the context
+//! that is active on that task.
+//!
+//! With the `opentelemetry` crate, an injector typically calls `inject_context` of the global text map
+//! propagator with `Context::current()` and a carrier that inserts into the given [`HeaderMap`], e.g. the
+//! `HeaderInjector` of `opentelemetry-http`. This adds the `traceparent` and `tracestate` headers.
+
+use hyper::http::HeaderMap;
+use std::fmt;
+
+/// Adds the headers of the trace context that is active on the current task to outgoing requests.
+pub trait Injector: fmt::Debug + Send + Sync + 'static {
+	/// Insert the headers of the current context, e.g. `traceparent` and `tracestate`, into `headers`.
+	///
+	/// The headers already contain the headers configured on the client.
+	fn inject(&self, headers: &mut HeaderMap);
+}
@@ -0,0 +1,214 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Progress of a single request, e.g. to show it in a user interface.
+//!
+//! Create a [`ProgressHandle`] with [`HttpTransportClient::send_progress`](crate::transport::HttpTransportClient::send_progress)
+//! or pass one in [`RequestOptions::progress`](crate::transport::RequestOptions::progress), and read it whenever
+//! convenient, e.g. once per frame of an event loop, while the request is running.
+
+use crate::executor::Executor;
+use hyper::body::{Body, Buf, HttpBody, SizeHint};
+use hyper::http::HeaderMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Max size of the request body chunks, such that the bytes sent advance in steps for large bodies.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Phase of a request, see [`ProgressHandle::phase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Phase {
+	/// Waiting for a connection, including for a redirect or a retry.
+	Connecting,
+	/// Sending the request body.
+	Sending,
+	/// Waiting for the response headers.
+	Waiting,
+	/// Reading the response body.
+	Receiving,
+	/// The request is done, successfully or not.
+	Done,
+}
+
+impl Phase {
+	fn from_u8(phase: u8) -> Self {
+		match phase {
+			0 => Self::Connecting,
+			1 => Self::Sending,
+			2 => Self::Waiting,
+			3 => Self::Receiving,
+			_ => Self::Done,
+		}
+	}
+}
+
+/// Handle to read the progress of a request.
+///
+/// Clones share the progress of the same request. The counters are updated by the transport as the request
+/// runs and reading them never blocks.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHandle(Arc<State>);
+
+#[derive(Debug, Default)]
+struct State {
+	phase: AtomicU8,
+	sent: AtomicU64,
+	received: AtomicU64,
+	/// Notified when the phase changes.
+	changed: tokio::sync::Notify,
+}
+
+impl ProgressHandle {
+	/// Create a handle of a request that hasn't started yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Current phase of the request.
+	pub fn phase(&self) -> Phase {
+		Phase::from_u8(self.0.phase.load(Ordering::Relaxed))
+	}
+
+	/// Number of request body bytes sent by the current attempt.
+	///
+	/// The count starts over for each redirect and retry.
+	pub fn bytes_sent(&self) -> u64 {
+		self.0.sent.load(Ordering::Relaxed)
+	}
+
+	/// Number of response body bytes received, before decompression.
+	pub fn bytes_received(&self) -> u64 {
+		self.0.received.load(Ordering::Relaxed)
+	}
+
+	/// A new attempt of the request is about to connect.
+	pub(crate) fn start_attempt(&self) {
+		self.0.sent.store(0, Ordering::Relaxed);
+		self.set_phase(Phase::Connecting);
+	}
+
+	/// The response headers arrived and the body is about to be read.
+	pub(crate) fn start_receiving(&self) {
+		self.set_phase(Phase::Receiving);
+	}
+
+	pub(crate) fn finish(&self) {
+		self.set_phase(Phase::Done);
+	}
+
+	/// Wait until the request body was sent or the request is done.
+	pub(crate) async fn sent(&self) {
+		loop {
+			// The notification is received by futures created before it.
+			let changed = self.0.changed.notified();
+			if self.phase() as u8 >= Phase::Waiting as u8 {
+				return;
+			}
+			changed.await;
+		}
+	}
+
+	fn set_phase(&self, phase: Phase) {
+		self.0.phase.store(phase as u8, Ordering::Relaxed);
+		self.0.changed.notify_waiters();
+	}
+
+	/// Move from the phase `from` to `to`, unless the request moved on already, e.g. because the server
+	/// responded before the whole request body was sent.
+	fn advance(&self, from: Phase, to: Phase) {
+		if self.0.phase.compare_exchange(from as u8, to as u8, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+			self.0.changed.notify_waiters();
+		}
+	}
+
+	/// Request body that counts the bytes sent.
+	///
+	/// hyper only polls the body once the connection is established, which starts the [`Phase::Sending`].
+	/// The body doesn't know its length anymore, so the caller must set the `Content-Length` header.
+	pub(crate) fn track_request(&self, mut body: Body, executor: &Arc<dyn Executor>) -> Body {
+		let (mut tx, rx) = Body::channel();
+		let progress = self.clone();
+
+		executor.spawn(Box::pin(async move {
+			if futures_util::future::poll_fn(|cx| tx.poll_ready(cx)).await.is_err() {
+				return;
+			}
+			progress.advance(Phase::Connecting, Phase::Sending);
+			while let Some(Ok(mut data)) = body.data().await {
+				while !data.is_empty() {
+					let chunk = data.split_to(std::cmp::min(data.len(), CHUNK_SIZE));
+					let len = chunk.len() as u64;
+					if tx.send_data(chunk).await.is_err() {
+						return;
+					}
+					progress.0.sent.fetch_add(len, Ordering::Relaxed);
+				}
+			}
+			progress.advance(Phase::Sending, Phase::Waiting);
+		}));
+
+		rx
+	}
+
+	/// Response body that counts the bytes received.
+	pub(crate) fn track_response<B: HttpBody>(progress: Option<&Self>, body: B) -> TrackedBody<B> {
+		TrackedBody { inner: Box::pin(body), progress: progress.cloned() }
+	}
+}
+
+/// Response body that counts the bytes received, see [`ProgressHandle::track_response`].
+pub(crate) struct TrackedBody<B> {
+	inner: Pin<Box<B>>,
+	progress: Option<ProgressHandle>,
+}
+
+impl<B: HttpBody> HttpBody for TrackedBody<B> {
+	type Data = B::Data;
+	type Error = B::Error;
+
+	fn poll_data(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+		let data = futures_util::ready!(self.inner.as_mut().poll_data(cx));
+		if let (Some(Ok(data)), Some(progress)) = (&data, &self.progress) {
+			progress.0.received.fetch_add(data.remaining() as u64, Ordering::Relaxed);
+		}
+		Poll::Ready(data)
+	}
+
+	fn poll_trailers(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+		self.inner.as_mut().poll_trailers(cx)
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
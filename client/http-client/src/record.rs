@@ -0,0 +1,333 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Record and replay HTTP exchanges for deterministic tests.
+//!
+//! [`RecordLayer`] wraps the HTTP backend and appends every request body together with the
+//! response it got to a file, one JSON object per line. [`ReplayService`] reads such a file and
+//! answers requests with the recorded responses instead of connecting to a server.
+//!
+//! Response bodies are passed on to the transport while they are recorded, so the limits of the transport
+//! still apply. An exchange is written once the transport has read the body to the end, exchanges whose
+//! body wasn't read to the end or is larger than [`RecordLayer::max_body_size`] aren't recorded.
+//!
+//! ```no_run
+//! use jsonrpsee_http_client::record::{RecordLayer, ReplayService};
+//! use jsonrpsee_http_client::HttpClientBuilder;
+//!
+//! // Capture the traffic of a client...
+//! let middleware = tower::ServiceBuilder::new().layer(RecordLayer::new("exchanges.jsonl").unwrap());
+//! let client = HttpClientBuilder::default().set_http_middleware(middleware).build("http://localhost:9933").unwrap();
+//!
+//! // ...and serve it back later without a server.
+//! let middleware = tower::ServiceBuilder::new().layer(ReplayService::from_file("exchanges.jsonl").unwrap());
+//! let client = HttpClientBuilder::default().set_http_middleware(middleware).build("http://localhost:9933").unwrap();
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use hyper::http::{HeaderName, HeaderValue};
+use hyper::Body;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+
+use crate::tee::tee;
+use crate::transport::Error;
+
+/// A request body together with the response the server answered it with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exchange {
+	/// Body of the request.
+	pub request: String,
+	/// Status code of the response.
+	pub status: u16,
+	/// Headers of the response in the order they were received.
+	pub headers: Vec<(String, String)>,
+	/// Body of the response.
+	pub body: String,
+}
+
+impl Exchange {
+	fn to_response(&self) -> Result<hyper::Response<Body>, Error> {
+		let mut response = hyper::Response::builder().status(self.status);
+		for (name, value) in &self.headers {
+			let name = HeaderName::try_from(name.as_str()).map_err(|e| Error::Http(Box::new(e)))?;
+			let value = HeaderValue::try_from(value.as_str()).map_err(|e| Error::Http(Box::new(e)))?;
+			response = response.header(name, value);
+		}
+		response.body(Body::from(self.body.clone())).map_err(|e| Error::Http(Box::new(e)))
+	}
+}
+
+/// Layer that records every exchange of the wrapped service to a file.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+	file: Arc<Mutex<File>>,
+	max_body_size: usize,
+}
+
+impl RecordLayer {
+	/// Record exchanges to the file at `path`, which is created or truncated.
+	pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+		Ok(Self { file: Arc::new(Mutex::new(File::create(path)?)), max_body_size: TEN_MB_SIZE_BYTES as usize })
+	}
+
+	/// Only record exchanges whose response body has at most this many bytes (default is 10 MiB).
+	pub fn max_body_size(mut self, max: usize) -> Self {
+		self.max_body_size = max;
+		self
+	}
+}
+
+impl<S> Layer<S> for RecordLayer {
+	type Service = Recorder<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		Recorder { inner, config: self.clone() }
+	}
+}
+
+/// Service that records every exchange of the wrapped service to a file, see [`RecordLayer`].
+#[derive(Debug, Clone)]
+pub struct Recorder<S> {
+	inner: S,
+	config: RecordLayer,
+}
+
+impl<S> Service<hyper::Request<Body>> for Recorder<S>
+where
+	S: Service<hyper::Request<Body>, Response = hyper::Response<Body>, Error = Error> + Clone + Send + 'static,
+	S::Future: Send,
+{
+	type Response = hyper::Response<Body>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(ctx)
+	}
+
+	fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+		// Use the service that was driven to readiness and leave the clone in its place.
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let config = self.config.clone();
+
+		Box::pin(async move {
+			let (parts, body) = req.into_parts();
+			let request = hyper::body::to_bytes(body).await?;
+			let response = inner.call(hyper::Request::from_parts(parts, Body::from(request.clone()))).await?;
+
+			let (parts, body) = response.into_parts();
+			let mut exchange = Exchange {
+				request: String::from_utf8_lossy(&request).into_owned(),
+				status: parts.status.as_u16(),
+				headers: parts
+					.headers
+					.iter()
+					.map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+					.collect(),
+				body: String::new(),
+			};
+			let body = tee(body, config.max_body_size, move |body| {
+				if !body.complete || body.truncated() {
+					tracing::warn!("Exchange not recorded, {} bytes of the response body were read", body.size);
+					return;
+				}
+				exchange.body = String::from_utf8_lossy(&body.bytes).into_owned();
+				let mut line = serde_json::to_string(&exchange).expect("Exchange is valid JSON; qed");
+				line.push('\n');
+
+				let mut file = config.file.lock().expect("lock poisoned; qed");
+				if let Err(err) = file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+					tracing::warn!("Failed to record exchange: {err}");
+				}
+			});
+
+			Ok(hyper::Response::from_parts(parts, body))
+		})
+	}
+}
+
+/// Service that answers requests with previously recorded exchanges.
+///
+/// Requests are matched on their body. If the same body was recorded several times, the responses
+/// are served in the order they were recorded and the last one is repeated once all others have been
+/// served. A request that doesn't match any recorded exchange fails with [`Error::Http`].
+///
+/// It can be used as a [`Layer`] which replaces the HTTP backend entirely.
+#[derive(Debug, Clone)]
+pub struct ReplayService {
+	exchanges: Arc<Mutex<HashMap<String, VecDeque<Exchange>>>>,
+}
+
+impl ReplayService {
+	/// Replay the given exchanges.
+	pub fn new(exchanges: impl IntoIterator<Item = Exchange>) -> Self {
+		let mut by_request: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+		for exchange in exchanges {
+			by_request.entry(exchange.request.clone()).or_default().push_back(exchange);
+		}
+		Self { exchanges: Arc::new(Mutex::new(by_request)) }
+	}
+
+	/// Replay the exchanges recorded to the file at `path` by [`RecordLayer`].
+	pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+		let mut exchanges = Vec::new();
+		for line in BufReader::new(File::open(path)?).lines() {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue;
+			}
+			exchanges.push(serde_json::from_str(&line)?);
+		}
+		Ok(Self::new(exchanges))
+	}
+
+	fn next_response(&self, request: &str) -> Result<hyper::Response<Body>, Error> {
+		let mut exchanges = self.exchanges.lock().expect("lock poisoned; qed");
+		let recorded = exchanges
+			.get_mut(request)
+			.ok_or_else(|| Error::Http(format!("No recorded exchange for request: {request}").into()))?;
+
+		if recorded.len() > 1 {
+			recorded.pop_front().expect("length checked above; qed").to_response()
+		} else {
+			recorded.front().expect("entries are never empty; qed").to_response()
+		}
+	}
+}
+
+impl<S> Layer<S> for ReplayService {
+	type Service = ReplayService;
+
+	fn layer(&self, _inner: S) -> Self::Service {
+		self.clone()
+	}
+}
+
+impl Service<hyper::Request<Body>> for ReplayService {
+	type Response = hyper::Response<Body>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+		let this = self.clone();
+
+		Box::pin(async move {
+			let request = hyper::body::to_bytes(req.into_body()).await?;
+			this.next_response(&String::from_utf8_lossy(&request))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::HttpClientBuilder;
+	use jsonrpsee_core::client::ClientT;
+	use jsonrpsee_core::rpc_params;
+	use jsonrpsee_test_utils::helpers::{http_server_with_hardcoded_response, ok_response};
+	use jsonrpsee_test_utils::mocks::Id;
+
+	fn exchange(request: &str, body: &str) -> Exchange {
+		Exchange { request: request.into(), status: 200, headers: Vec::new(), body: body.into() }
+	}
+
+	#[tokio::test]
+	async fn record_then_replay_works() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-record-{}.jsonl", std::process::id()));
+
+		let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0))).await;
+		let middleware = tower::ServiceBuilder::new().layer(RecordLayer::new(&path).unwrap());
+		let client = HttpClientBuilder::default()
+			.set_http_middleware(middleware)
+			.build(format!("http://{server_addr}"))
+			.unwrap();
+		let recorded: String = client.request("say_hello", rpc_params![]).await.unwrap();
+
+		let middleware = tower::ServiceBuilder::new().layer(ReplayService::from_file(&path).unwrap());
+		let client = HttpClientBuilder::default().set_http_middleware(middleware).build("http://localhost:1").unwrap();
+		let replayed: String = client.request("say_hello", rpc_params![]).await.unwrap();
+
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(recorded, "hello");
+		assert_eq!(replayed, recorded);
+	}
+
+	#[tokio::test]
+	async fn exchanges_not_read_to_the_end_are_not_recorded() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-record-partial-{}.jsonl", std::process::id()));
+
+		let server_addr =
+			http_server_with_hardcoded_response(ok_response("hello".repeat(100).into(), Id::Num(0))).await;
+		let record = RecordLayer::new(&path).unwrap();
+		let client = |max_response_size| {
+			HttpClientBuilder::default()
+				.max_response_size(max_response_size)
+				.set_http_middleware(tower::ServiceBuilder::new().layer(record.clone()))
+				.build(format!("http://{server_addr}"))
+				.unwrap()
+		};
+		assert!(client(100).request::<String, _>("say_hello", rpc_params![]).await.is_err());
+		let _: String = client(1024).request("say_hello", rpc_params![]).await.unwrap();
+
+		let recorded = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(recorded.lines().count(), 1);
+	}
+
+	#[tokio::test]
+	async fn replay_serves_repeated_requests_in_order() {
+		let mut replay = ReplayService::new([exchange("a", "1"), exchange("a", "2"), exchange("b", "3")]);
+
+		for expected in ["1", "2", "2"] {
+			let response = replay.call(hyper::Request::new(Body::from("a"))).await.unwrap();
+			assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), expected);
+		}
+		let response = replay.call(hyper::Request::new(Body::from("b"))).await.unwrap();
+		assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "3");
+	}
+
+	#[tokio::test]
+	async fn replay_unknown_request_fails() {
+		let mut replay = ReplayService::new([exchange("a", "1")]);
+		let err = replay.call(hyper::Request::new(Body::from("c"))).await.unwrap_err();
+		assert!(matches!(err, Error::Http(_)));
+	}
+}
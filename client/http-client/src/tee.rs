@@ -0,0 +1,104 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Capture of response bodies while they are passed on, see [`tee`].
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use hyper::body::{Bytes, HttpBody};
+use hyper::Body;
+
+/// Bytes of a body captured by [`tee`].
+#[derive(Debug, Default)]
+pub(crate) struct Captured {
+	/// The first bytes of the body, at most as many as the limit of the capture.
+	pub(crate) bytes: Vec<u8>,
+	/// Number of bytes passed on, which may be more than were captured.
+	pub(crate) size: usize,
+	/// Whether the body was read to the end, rather than failed or dropped.
+	pub(crate) complete: bool,
+}
+
+impl Captured {
+	/// Whether bytes were passed on that weren't captured.
+	pub(crate) fn truncated(&self) -> bool {
+		self.size > self.bytes.len()
+	}
+}
+
+/// Pass `body` on and capture its first `limit` bytes, which are handed to `done` once the body was read to
+/// the end, failed or was dropped.
+///
+/// The body is streamed, so the reader still applies its own limits, e.g. the max response size of the
+/// transport, and stops reading the body whenever it wants to.
+pub(crate) fn tee(body: Body, limit: usize, done: impl FnOnce(Captured) + Send + 'static) -> Body {
+	Body::wrap_stream(Tee { body, limit, captured: Captured::default(), done: Some(Box::new(done)) })
+}
+
+struct Tee {
+	body: Body,
+	limit: usize,
+	captured: Captured,
+	done: Option<Box<dyn FnOnce(Captured) + Send>>,
+}
+
+impl Tee {
+	fn finish(&mut self) {
+		if let Some(done) = self.done.take() {
+			done(std::mem::take(&mut self.captured));
+		}
+	}
+}
+
+impl Stream for Tee {
+	type Item = Result<Bytes, hyper::Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		let data = futures_util::ready!(Pin::new(&mut this.body).poll_data(cx));
+		match &data {
+			Some(Ok(data)) => {
+				let room = this.limit.saturating_sub(this.captured.bytes.len());
+				this.captured.bytes.extend_from_slice(&data[..room.min(data.len())]);
+				this.captured.size += data.len();
+			}
+			Some(Err(_)) => this.finish(),
+			None => {
+				this.captured.complete = true;
+				this.finish();
+			}
+		}
+		Poll::Ready(data)
+	}
+}
+
+impl Drop for Tee {
+	fn drop(&mut self) {
+		self.finish();
+	}
+}
@@ -24,9 +24,13 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::types::error::{ErrorCode, ErrorObject};
+use crate::types::error::{ErrorCode, ErrorObject, INTERNAL_ERROR_CODE};
 
-use crate::HttpClientBuilder;
+use std::time::Duration;
+
+use crate::transport::PoolConfig;
+use crate::{BatchIdPolicy, HttpClientBuilder};
+use futures_util::StreamExt;
 use jsonrpsee_core::client::{BatchResponse, ClientT, IdKind};
 use jsonrpsee_core::params::BatchRequestBuilder;
 use jsonrpsee_core::Error;
@@ -35,6 +39,7 @@ use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::Id;
 use jsonrpsee_test_utils::TimeoutFutureExt;
 use jsonrpsee_types::error::ErrorObjectOwned;
+use jsonrpsee_types::InvalidRequestId;
 
 fn init_logger() {
 	let _ = tracing_subscriber::FmtSubscriber::builder()
@@ -89,6 +94,226 @@ async fn notification_works() {
 		.unwrap();
 }
 
+#[tokio::test]
+async fn keep_alive_pings_idle_connection() {
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(String::new()).with_default_timeout().await.unwrap();
+	let uri = format!("http://{server_addr}");
+	let _client = HttpClientBuilder::default().keep_alive(Duration::from_millis(50), "ping").build(&uri).unwrap();
+
+	for _ in 0..2 {
+		let ping = requests.next().with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(ping.body(), r#"{"jsonrpc":"2.0","method":"ping"}"#);
+	}
+}
+
+#[tokio::test]
+async fn with_target_shares_connections_and_ids() {
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(ok_response("hello".into(), Id::Num(0)))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let client = HttpClientBuilder::default()
+		.allow_connection_reuse(true)
+		.ignore_response_id(true)
+		.build_retargetable(format!("http://{server_addr}/a"))
+		.unwrap();
+	let other = client.with_target(format!("http://{server_addr}/b")).unwrap();
+
+	let mut sent = Vec::new();
+	for client in [&client, &other] {
+		let _: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+		let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+		let body: serde_json::Value = serde_json::from_str(request.body()).unwrap();
+		sent.push((
+			request.uri().path().to_owned(),
+			body["id"].clone(),
+			request.extensions().get::<std::net::SocketAddr>().copied(),
+		));
+	}
+	assert_eq!((sent[0].0.as_str(), sent[1].0.as_str()), ("/a", "/b"));
+	assert_eq!((sent[0].1.as_u64(), sent[1].1.as_u64()), (Some(0), Some(1)));
+	assert_eq!(sent[0].2, sent[1].2);
+}
+
+#[tokio::test]
+async fn keep_alive_stops_when_client_is_dropped() {
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(String::new()).with_default_timeout().await.unwrap();
+	let uri = format!("http://{server_addr}");
+	let client = HttpClientBuilder::default().keep_alive(Duration::from_millis(50), "ping").build(&uri).unwrap();
+	drop(client);
+
+	assert!(requests.next().with_timeout(Duration::from_millis(200)).await.is_err());
+}
+
+#[tokio::test]
+async fn keep_alive_does_not_ping_during_requests() {
+	let (server_addr, mut requests) = http_server_with_delayed_response_and_request_log(
+		ok_response("hello".into(), Id::Num(0)),
+		Duration::from_millis(300),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let uri = format!("http://{server_addr}");
+	let client = HttpClientBuilder::default().keep_alive(Duration::from_millis(50), "ping").build(&uri).unwrap();
+
+	let _: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	let call = requests.next().with_default_timeout().await.unwrap().unwrap();
+	assert!(call.body().contains("say_hello"));
+	assert!(requests.try_recv().is_err(), "pinged while the request was in flight");
+}
+
+#[tokio::test]
+async fn keep_alive_ping_times_out() {
+	let (server_addr, mut requests) =
+		http_server_with_delayed_response_and_request_log(String::new(), Duration::from_secs(60))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let uri = format!("http://{server_addr}");
+	let _client = HttpClientBuilder::default()
+		.request_timeout_per_hop(Duration::from_millis(100))
+		.keep_alive(Duration::from_millis(50), "ping")
+		.build(&uri)
+		.unwrap();
+
+	// The server never responds, the pings go on after each one timed out.
+	for _ in 0..2 {
+		let ping = requests.next().with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(ping.body(), r#"{"jsonrpc":"2.0","method":"ping"}"#);
+	}
+}
+
+#[tokio::test]
+async fn keep_alive_requires_connection_reuse() {
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(String::new()).with_default_timeout().await.unwrap();
+	let uri = format!("http://{server_addr}");
+	let _client = HttpClientBuilder::default()
+		.pool_config(PoolConfig { max_idle_per_host: 0, ..Default::default() })
+		.keep_alive(Duration::from_millis(50), "ping")
+		.build(&uri)
+		.unwrap();
+
+	assert!(requests.next().with_timeout(Duration::from_millis(200)).await.is_err());
+}
+
+#[tokio::test]
+async fn method_limits_apply_per_method() {
+	use crate::limits::{Limits, MethodLimits};
+
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{server_addr}");
+	let limits = MethodLimits::new(Limits { max_response_size: Some(10), ..Default::default() })
+		.with_method("big", Limits { max_response_size: Some(1000), ..Default::default() });
+	let client = HttpClientBuilder::default().method_limits(limits).build(&uri).unwrap();
+
+	let response: String = client.request("big", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, "hello");
+	let err = client.request::<String, _>("small", rpc_params![]).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Transport(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn with_headers_adds_headers_to_calls() {
+	use crate::{HeaderMap, HeaderValue};
+
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(ok_response("hello".into(), Id::Num(0)))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let uri = format!("http://{server_addr}");
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let mut headers = HeaderMap::new();
+	headers.insert("authorization", HeaderValue::from_static("Bearer token"));
+	let response: String = client.with_headers(headers).request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+	let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(request.headers()["authorization"], "Bearer token");
+
+	client.notification("say_hello", rpc_params![]).await.unwrap();
+	let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+	assert!(request.headers().get("authorization").is_none());
+}
+
+#[tokio::test]
+async fn call_options_send_idempotency_key() {
+	use crate::{CallOptions, HeaderValue};
+
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(ok_response("hello".into(), Id::Num(0)))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let client = HttpClientBuilder::default().build(format!("http://{server_addr}")).unwrap();
+
+	let options = CallOptions { idempotency_key: Some(HeaderValue::from_static("op-1")), ..Default::default() };
+	let response: String = client.with_call_options(options).request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+	let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(request.headers()["idempotency-key"], "op-1");
+
+	client.notification("say_hello", rpc_params![]).await.unwrap();
+	let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+	assert!(request.headers().get("idempotency-key").is_none());
+}
+
+#[tokio::test]
+async fn call_options_restrict_hosts() {
+	use crate::transport::Error as TransportError;
+	use crate::CallOptions;
+
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let client = HttpClientBuilder::default().build(format!("http://{server_addr}")).unwrap();
+	let allowed = |host: &str| CallOptions { allowed_hosts: Some([host.to_owned()].into()), ..Default::default() };
+
+	let response: String =
+		client.with_call_options(allowed("127.0.0.1")).request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+	let err = client.with_call_options(allowed("example.com")).request::<String, _>("say_hello", rpc_params![]).await;
+	match err.unwrap_err() {
+		Error::Transport(err) => {
+			assert!(matches!(err.downcast_ref::<TransportError>(), Some(TransportError::HostNotAllowed { .. })))
+		}
+		err => panic!("unexpected error: {err:?}"),
+	}
+}
+
+#[tokio::test]
+async fn call_options_select_connection_pool() {
+	use crate::CallOptions;
+
+	let (server_addr, mut requests) =
+		http_server_with_hardcoded_response_and_request_log(ok_response("hello".into(), Id::Num(0)))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let client = HttpClientBuilder::default().ignore_response_id(true).build(format!("http://{server_addr}")).unwrap();
+	let keyed =
+		|key: &str| client.with_call_options(CallOptions { pool_key: Some(key.to_owned()), ..Default::default() });
+
+	// Address of the client side of the connection each call was sent over.
+	let mut sent = Vec::new();
+	for client in [keyed("a"), keyed("b"), keyed("a")] {
+		let _: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+		let request = requests.next().with_default_timeout().await.unwrap().unwrap();
+		sent.push(request.extensions().get::<std::net::SocketAddr>().copied().unwrap());
+	}
+	assert_ne!(sent[0], sent[1]);
+	assert_eq!(sent[0], sent[2]);
+}
+
 #[tokio::test]
 async fn response_with_wrong_id() {
 	let err = run_request_with_response(ok_response("hello".into(), Id::Num(99)))
@@ -258,13 +483,94 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+fn two_call_batch() -> BatchRequestBuilder<'static> {
+	let mut batch_request = BatchRequestBuilder::new();
+	batch_request.insert("say_hello", rpc_params![]).unwrap();
+	batch_request.insert("say_goodbye", rpc_params![]).unwrap();
+	batch_request
+}
+
+#[tokio::test]
+async fn batch_request_missing_id() {
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+
+	let err = run_batch_request_with_response::<String>(two_call_batch(), server_response.clone())
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert!(matches!(err, Error::Custom(_)), "{err:?}");
+
+	let res = run_batch_request_with_policy::<String>(two_call_batch(), server_response, BatchIdPolicy::Lenient)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(res.num_successful_calls(), 1);
+	assert_eq!(res.num_failed_calls(), 1);
+	let mut responses = res.into_iter();
+	assert_eq!(responses.next().unwrap().unwrap(), "hello");
+	assert_eq!(responses.next().unwrap().unwrap_err().code(), INTERNAL_ERROR_CODE);
+}
+
+#[tokio::test]
+async fn batch_request_extra_id() {
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","result":"goodbye","id":1}, {"jsonrpc":"2.0","result":"surprise","id":7}]"#.to_string();
+
+	let err = run_batch_request_with_response::<String>(two_call_batch(), server_response.clone())
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert!(matches!(err, Error::InvalidRequestId(InvalidRequestId::NotPendingRequest(_))), "{err:?}");
+
+	let res = run_batch_request_with_policy::<String>(two_call_batch(), server_response, BatchIdPolicy::Lenient)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(res.num_successful_calls(), 2);
+	let response: Vec<_> = res.into_ok().unwrap().collect();
+	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string()]);
+}
+
+#[tokio::test]
+async fn batch_request_duplicate_id() {
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","result":"again","id":0}, {"jsonrpc":"2.0","result":"goodbye","id":1}]"#.to_string();
+
+	let err = run_batch_request_with_response::<String>(two_call_batch(), server_response.clone())
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert!(matches!(err, Error::InvalidRequestId(InvalidRequestId::Occupied(_))), "{err:?}");
+
+	let res = run_batch_request_with_policy::<String>(two_call_batch(), server_response, BatchIdPolicy::Lenient)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(res.num_successful_calls(), 2);
+	assert_eq!(res.num_failed_calls(), 0);
+	let response: Vec<_> = res.into_ok().unwrap().collect();
+	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string()]);
+}
+
 async fn run_batch_request_with_response<T: Send + DeserializeOwned + std::fmt::Debug + Clone + 'static>(
 	batch: BatchRequestBuilder<'_>,
 	response: String,
-) -> Result<BatchResponse<T>, Error> {
+) -> Result<BatchResponse<'_, T>, Error> {
+	run_batch_request_with_policy(batch, response, BatchIdPolicy::default()).await
+}
+
+async fn run_batch_request_with_policy<T: Send + DeserializeOwned + std::fmt::Debug + Clone + 'static>(
+	batch: BatchRequestBuilder<'_>,
+	response: String,
+	policy: BatchIdPolicy,
+) -> Result<BatchResponse<'_, T>, Error> {
 	let server_addr = http_server_with_hardcoded_response(response).with_default_timeout().await.unwrap();
 	let uri = format!("http://{server_addr}");
-	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let client = HttpClientBuilder::default().batch_id_policy(policy).build(&uri).unwrap();
 	client.batch_request(batch).with_default_timeout().await.unwrap()
 }
 
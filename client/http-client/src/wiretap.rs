@@ -0,0 +1,218 @@
+// Copyright 2019-2023 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tap of the exact bytes written to and read from the connections of the transport, for protocol-level
+//! debugging and fuzzing harnesses.
+//!
+//! Unlike the request and response logging, which sees the bodies after decompression and redirects, a
+//! [`WireTap`] sees the HTTP messages as they are on the connection: the request and status lines, the headers,
+//! chunked encoding and, for `https` targets, the encrypted TLS records. Register it with
+//! [`HttpTransportClientConfig::wire_tap`](crate::transport::HttpTransportClientConfig::wire_tap).
+//!
+//! The tap is invoked synchronously for every read and write, which adds a virtual call per I/O operation and
+//! whatever the tap does with the bytes, and disables vectored writes. It's meant for tests and debugging, not
+//! for production.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::{Connected, Connection};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tower::Service;
+
+/// Callbacks invoked with the bytes of each connection.
+///
+/// `connection` identifies the connection, it's unique among the connections made by the same transport.
+pub trait WireTap: fmt::Debug + Send + Sync + 'static {
+	/// `bytes` were written to the connection.
+	fn on_write(&self, connection: u64, bytes: &[u8]) {
+		let _ = (connection, bytes);
+	}
+
+	/// `bytes` were read from the connection.
+	fn on_read(&self, connection: u64, bytes: &[u8]) {
+		let _ = (connection, bytes);
+	}
+}
+
+/// Connector that passes the bytes of the connections made by the inner connector to a [`WireTap`].
+#[derive(Debug, Clone)]
+pub struct TapConnector<C> {
+	inner: C,
+	tap: Arc<dyn WireTap>,
+	next_connection: Arc<AtomicU64>,
+}
+
+impl<C> TapConnector<C> {
+	/// Tap the connections made by `inner`.
+	pub fn new(inner: C, tap: Arc<dyn WireTap>) -> Self {
+		Self { inner, tap, next_connection: Default::default() }
+	}
+}
+
+impl<C> Service<hyper::Uri> for TapConnector<C>
+where
+	C: Service<hyper::Uri>,
+	C::Future: Send + 'static,
+{
+	type Response = TapStream<C::Response>;
+	type Error = C::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, uri: hyper::Uri) -> Self::Future {
+		let connect = self.inner.call(uri);
+		let tap = self.tap.clone();
+		let connection = self.next_connection.fetch_add(1, Ordering::Relaxed);
+		Box::pin(async move { Ok(TapStream { inner: connect.await?, tap, connection }) })
+	}
+}
+
+/// Connection made by the [`TapConnector`].
+#[derive(Debug)]
+pub struct TapStream<T> {
+	inner: T,
+	tap: Arc<dyn WireTap>,
+	connection: u64,
+}
+
+impl<T: Connection> Connection for TapStream<T> {
+	fn connected(&self) -> Connected {
+		self.inner.connected()
+	}
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for TapStream<T> {
+	fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+		let filled = buf.filled().len();
+		let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+		if let Poll::Ready(Ok(())) = poll {
+			let read = &buf.filled()[filled..];
+			if !read.is_empty() {
+				self.tap.on_read(self.connection, read);
+			}
+		}
+		poll
+	}
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for TapStream<T> {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+		let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+		if let Poll::Ready(Ok(written)) = poll {
+			self.tap.on_write(self.connection, &buf[..written]);
+		}
+		poll
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		Pin::new(&mut self.inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		Pin::new(&mut self.inner).poll_shutdown(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::connector::CustomConnector;
+	use crate::transport::{HttpTransportClient, HttpTransportClientConfig};
+	use hyper::client::HttpConnector;
+	use jsonrpsee_test_utils::helpers::http_server_with_hardcoded_response;
+	use std::sync::Mutex;
+	use tokio::io::AsyncWriteExt;
+
+	/// Records the bytes of all connections.
+	#[derive(Debug, Default)]
+	struct Recorder {
+		written: Mutex<Vec<u8>>,
+		read: Mutex<Vec<u8>>,
+	}
+
+	impl WireTap for Recorder {
+		fn on_write(&self, _: u64, bytes: &[u8]) {
+			self.written.lock().unwrap().extend_from_slice(bytes);
+		}
+
+		fn on_read(&self, _: u64, bytes: &[u8]) {
+			self.read.lock().unwrap().extend_from_slice(bytes);
+		}
+	}
+
+	#[tokio::test]
+	async fn wire_tap_sees_the_raw_messages() {
+		const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n2\r\n[]\r\n0\r\n\r\n";
+
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let target = format!("http://{}", listener.local_addr().unwrap());
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+			let mut request = Vec::new();
+			while !request.ends_with(b"\r\n\r\n{}") {
+				let mut buf = [0; 1024];
+				let n = tokio::io::AsyncReadExt::read(&mut stream, &mut buf).await.unwrap();
+				request.extend_from_slice(&buf[..n]);
+			}
+			stream.write_all(RESPONSE).await.unwrap();
+		});
+
+		let recorder = Arc::new(Recorder::default());
+		let config = HttpTransportClientConfig { wire_tap: Some(recorder.clone()), ..Default::default() };
+		let client = HttpTransportClient::new(&target, config, tower::ServiceBuilder::new()).unwrap();
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[]");
+
+		let written = String::from_utf8(recorder.written.lock().unwrap().clone()).unwrap();
+		assert!(written.starts_with("POST / HTTP/1.1\r\n"), "{written}");
+		assert!(written.ends_with("\r\n\r\n{}"), "{written}");
+		assert_eq!(&recorder.read.lock().unwrap()[..], RESPONSE);
+	}
+
+	#[tokio::test]
+	async fn wire_tap_sees_the_connections_of_a_custom_connector() {
+		let addr = http_server_with_hardcoded_response("[]".into()).await;
+		let recorder = Arc::new(Recorder::default());
+		let config = HttpTransportClientConfig {
+			connector: Some(CustomConnector::new(HttpConnector::new())),
+			wire_tap: Some(recorder.clone()),
+			..Default::default()
+		};
+		let client = HttpTransportClient::new(&format!("http://{addr}"), config, tower::ServiceBuilder::new()).unwrap();
+		assert_eq!(client.send_and_read_body("{}".into()).await.unwrap(), b"[]");
+
+		let written = String::from_utf8(recorder.written.lock().unwrap().clone()).unwrap();
+		assert!(written.starts_with("POST / HTTP/1.1\r\n"), "{written}");
+		assert!(!recorder.read.lock().unwrap().is_empty());
+	}
+}
@@ -26,8 +26,10 @@
 
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use crate::mocks::{Body, HttpResponse, Id, Uri};
+use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Client, Request, Response, Server};
 use serde::Serialize;
@@ -240,3 +242,58 @@ pub async fn http_server_with_hardcoded_response(response: String) -> SocketAddr
 
 	rx.await.unwrap()
 }
+
+/// Spawn HTTP server that responds with a hardcoded response and forwards every request it receives
+/// to the returned channel.
+///
+/// The address of the peer is stored as `SocketAddr` in the extensions of each request.
+//
+// NOTE: This must be spawned on tokio because hyper only works with tokio.
+pub async fn http_server_with_hardcoded_response_and_request_log(
+	response: String,
+) -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<Request<String>>) {
+	http_server_with_delayed_response_and_request_log(response, Duration::ZERO).await
+}
+
+/// Like [`http_server_with_hardcoded_response_and_request_log`], but the response to each request is sent
+/// `delay` after the request was received.
+//
+// NOTE: This must be spawned on tokio because hyper only works with tokio.
+pub async fn http_server_with_delayed_response_and_request_log(
+	response: String,
+	delay: Duration,
+) -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<Request<String>>) {
+	let (req_tx, req_rx) = futures_channel::mpsc::unbounded();
+
+	let make_service = make_service_fn(move |conn: &AddrStream| {
+		let remote_addr = conn.remote_addr();
+		let response = response.clone();
+		let req_tx = req_tx.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+				let response = response.clone();
+				let req_tx = req_tx.clone();
+				async move {
+					let (mut parts, body) = req.into_parts();
+					parts.extensions.insert(remote_addr);
+					let body = hyper::body::to_bytes(body).await.unwrap();
+					let _ =
+						req_tx.unbounded_send(Request::from_parts(parts, String::from_utf8_lossy(&body).into_owned()));
+					tokio::time::sleep(delay).await;
+					Ok::<_, Infallible>(Response::new(hyper::Body::from(response)))
+				}
+			}))
+		}
+	});
+
+	let (tx, rx) = futures_channel::oneshot::channel::<SocketAddr>();
+
+	tokio::spawn(async {
+		let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+		let server = Server::bind(&addr).serve(make_service);
+		tx.send(server.local_addr()).unwrap();
+		server.await.unwrap()
+	});
+
+	(rx.await.unwrap(), req_rx)
+}